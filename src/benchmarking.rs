@@ -0,0 +1,181 @@
+//! Benchmarking setup for pallet-rbac
+
+use super::*;
+
+use crate::Pallet as RBAC;
+use frame_benchmarking::v2::*;
+use frame_support::{
+    sp_runtime::traits::StaticLookup, sp_std::vec::Vec, storage_alias, Blake2_128Concat, BoundedVec,
+};
+use frame_system::RawOrigin;
+
+/// Reverse index of the role holders. It is not a part of the pallet yet,
+/// it exists only to compare listing through it with the scan of `Assignments`.
+#[storage_alias]
+type RoleMembers<T: Config> = StorageDoubleMap<
+    Pallet<T>,
+    Blake2_128Concat,
+    <T as Config>::RoleId,
+    Blake2_128Concat,
+    <T as frame_system::Config>::AccountId,
+    (),
+>;
+
+/// Create a role with `n` holders, optionally writing them to the reverse index
+fn setup_holders<T: Config>(n: u32, with_index: bool) -> T::RoleId {
+    let role_id =
+        RBAC::<T>::add_role("bench".as_bytes(), &[], false).expect("role should be valid");
+    for i in 0..n {
+        let holder: T::AccountId = account("holder", i, 0);
+        RBAC::<T>::preassign_role(holder.clone(), role_id).expect("role should exist");
+        if with_index {
+            RoleMembers::<T>::insert(role_id, holder, ());
+        }
+    }
+    role_id
+}
+
+/// Create a role with `g` granters and `c` conflicts, returning it with an account holding only
+/// the last granter, so every granter is checked
+fn setup_granter<T: Config>(g: u32, c: u32) -> (T::AccountId, T::RoleId) {
+    let granters: Vec<_> = (0..g)
+        .map(|_| {
+            RBAC::<T>::add_role("granter".as_bytes(), &[], false).expect("role should be valid")
+        })
+        .collect();
+    let role_id =
+        RBAC::<T>::add_role("bench".as_bytes(), &granters, false).expect("role should be valid");
+    let conflicts: Vec<_> = (0..c)
+        .map(|_| {
+            RBAC::<T>::add_role("conflict".as_bytes(), &[], false).expect("role should be valid")
+        })
+        .collect();
+    Roles::<T>::mutate(role_id, |role| {
+        if let Some(role) = role {
+            role.conflicts = conflicts.try_into().expect("conflicts should fit");
+        }
+    });
+    let caller: T::AccountId = whitelisted_caller();
+    RBAC::<T>::preassign_role(caller.clone(), granters[granters.len() - 1])
+        .expect("role should exist");
+    (caller, role_id)
+}
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn list_role_holders_scan(n: Linear<1, 1_000>) {
+        let role_id = setup_holders::<T>(n, false);
+        let holders;
+
+        #[block]
+        {
+            holders = Assignments::<T>::iter_keys()
+                .filter(|(_, role)| *role == role_id)
+                .count();
+        }
+
+        assert_eq!(holders, n as usize);
+    }
+
+    #[benchmark]
+    fn list_role_holders_index(n: Linear<1, 1_000>) {
+        let role_id = setup_holders::<T>(n, true);
+        let holders;
+
+        #[block]
+        {
+            holders = RoleMembers::<T>::iter_key_prefix(role_id).count();
+        }
+
+        assert_eq!(holders, n as usize);
+    }
+
+    #[benchmark]
+    fn add_role(g: Linear<0, { T::GrantersListMaxLength::get() }>) {
+        let granters: Vec<_> = (0..g)
+            .map(|_| {
+                RBAC::<T>::add_role("granter".as_bytes(), &[], false).expect("role should be valid")
+            })
+            .collect();
+        let role_id;
+
+        #[block]
+        {
+            role_id = RBAC::<T>::add_role("bench".as_bytes(), &granters, false)
+                .expect("role should be valid");
+        }
+
+        assert_eq!(
+            RBAC::<T>::roles(role_id).map(|role| role.granters.len()),
+            Some(g as usize)
+        );
+    }
+
+    #[benchmark]
+    fn grant_role(n: Linear<1, { T::GrantersListMaxLength::get() }>) {
+        // the caller holds only the last granter, so every granter is checked
+        let granters: Vec<_> = (0..n)
+            .map(|_| {
+                RBAC::<T>::add_role("granter".as_bytes(), &[], false).expect("role should be valid")
+            })
+            .collect();
+        let role_id = RBAC::<T>::add_role("bench".as_bytes(), &granters, false)
+            .expect("role should be valid");
+        let caller: T::AccountId = whitelisted_caller();
+        RBAC::<T>::preassign_role(caller.clone(), granters[granters.len() - 1])
+            .expect("role should exist");
+        let user: T::AccountId = account("user", 0, 0);
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            T::Lookup::unlookup(user.clone()),
+            role_id,
+        );
+
+        assert!(Assignments::<T>::contains_key(user, role_id));
+    }
+
+    #[benchmark]
+    fn grant_role_batch(
+        n: Linear<1, { T::BatchMaxLength::get() }>,
+        g: Linear<1, { T::GrantersListMaxLength::get() }>,
+        c: Linear<0, { T::MaxConflicts::get() }>,
+    ) {
+        let (caller, role_id) = setup_granter::<T>(g, c);
+        let users: Vec<T::AccountId> = (0..n).map(|i| account("user", i, 0)).collect();
+        let batch: BoundedVec<_, _> = users.clone().try_into().expect("batch should fit");
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), batch, role_id);
+
+        assert!(users
+            .iter()
+            .all(|user| Assignments::<T>::contains_key(user, role_id)));
+    }
+
+    #[benchmark]
+    fn revoke_role_batch(
+        n: Linear<1, { T::BatchMaxLength::get() }>,
+        g: Linear<1, { T::GrantersListMaxLength::get() }>,
+    ) {
+        let (caller, role_id) = setup_granter::<T>(g, 0);
+        let users: Vec<T::AccountId> = (0..n).map(|i| account("user", i, 0)).collect();
+        for user in users.iter() {
+            RBAC::<T>::preassign_role(user.clone(), role_id).expect("role should exist");
+        }
+        let batch: BoundedVec<_, _> = users.clone().try_into().expect("batch should fit");
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), batch, role_id);
+
+        assert!(users
+            .iter()
+            .all(|user| !Assignments::<T>::contains_key(user, role_id)));
+    }
+
+    impl_benchmark_test_suite!(RBAC, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,168 @@
+use crate::{CallRoleMap, Config, Error, Pallet, RoleCallFilter};
+use codec::{Decode, Encode};
+use frame_support::{
+    dispatch::DispatchResult,
+    sp_runtime::{
+        traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension},
+        transaction_validity::{
+            InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+        },
+    },
+    sp_std::{fmt, marker::PhantomData},
+    traits::Contains,
+    CloneNoBound, EqNoBound, PartialEqNoBound,
+};
+use scale_info::TypeInfo;
+
+/// Signed extension counting failed grant attempts.
+///
+/// A failed call doesn't keep its storage changes, so the pallet can't count the attempt itself
+/// and it is counted after the dispatch: every signed transaction failing with `NotAuthorizedToGrant`
+/// is counted against its signer. This includes the wrappers returning the error of the wrapped call,
+/// e.g. `utility.batch_all` or `multisig.as_multi_threshold_1`.
+///
+/// The counting is best-effort: wrappers that succeed and only report the wrapped failure in an event,
+/// e.g. `utility.batch` or `proxy.proxy`, hide it. Runtimes relying on the lockout should keep
+/// the grant calls out of such wrappers with their `BaseCallFilter`. The lockout itself is checked
+/// by the pallet, so it applies to every dispatch route.
+/// Add it to the runtime's `SignedExtra` to enable the brute-force protection of `grant_role`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckGrantAttempts<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckGrantAttempts<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Config + Send + Sync> Default for CheckGrantAttempts<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckGrantAttempts<T> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CheckGrantAttempts")
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckGrantAttempts<T> {
+    const IDENTIFIER: &'static str = "CheckGrantAttempts";
+    type AccountId = T::AccountId;
+    type Call = T::RuntimeCall;
+    type AdditionalSigned = ();
+    /// Signer of the transaction, it may try to grant a role through a wrapper
+    type Pre = T::AccountId;
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        _call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        Ok(who.clone())
+    }
+
+    fn post_dispatch(
+        pre: Option<Self::Pre>,
+        _info: &DispatchInfoOf<Self::Call>,
+        _post_info: &PostDispatchInfoOf<Self::Call>,
+        _len: usize,
+        result: &DispatchResult,
+    ) -> Result<(), TransactionValidityError> {
+        if let Some(who) = pre {
+            if *result == Err(Error::<T>::NotAuthorizedToGrant.into()) {
+                Pallet::<T>::note_failed_grant(&who);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Custom `InvalidTransaction` code of the transactions rejected by `CheckRole`
+pub const ROLE_REQUIRED: u8 = 1;
+
+/// Signed extension rejecting the transactions whose signer lacks the role required for the call.
+///
+/// The roles of the calls are configured by `Map`, the calls that are not in it are always valid.
+/// The check is done in the transaction pool, so unauthorized calls don't take the block space.
+/// Add it to the runtime's `SignedExtra`, e.g. `CheckRole<Runtime, CallRoles>`.
+#[derive(Encode, Decode, CloneNoBound, EqNoBound, PartialEqNoBound, TypeInfo)]
+#[scale_info(skip_type_params(T, Map))]
+pub struct CheckRole<T: Config + Send + Sync, Map>(PhantomData<(T, Map)>);
+
+impl<T: Config + Send + Sync, Map> CheckRole<T, Map> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Config + Send + Sync, Map> Default for CheckRole<T, Map> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Config + Send + Sync, Map> fmt::Debug for CheckRole<T, Map> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CheckRole")
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl<T, Map> SignedExtension for CheckRole<T, Map>
+where
+    T: Config + Send + Sync,
+    Map: CallRoleMap<T::RuntimeCall, T::RoleId> + Send + Sync + 'static,
+{
+    const IDENTIFIER: &'static str = "CheckRole";
+    type AccountId = T::AccountId;
+    type Call = T::RuntimeCall;
+    type AdditionalSigned = ();
+    type Pre = ();
+
+    fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        _info: &DispatchInfoOf<Self::Call>,
+        _len: usize,
+    ) -> TransactionValidity {
+        if !RoleCallFilter::<T, Map>::contains(&(who.clone(), call.clone())) {
+            Err(InvalidTransaction::Custom(ROLE_REQUIRED))?
+        }
+        Ok(ValidTransaction::default())
+    }
+
+    fn pre_dispatch(
+        self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: &DispatchInfoOf<Self::Call>,
+        len: usize,
+    ) -> Result<Self::Pre, TransactionValidityError> {
+        self.validate(who, call, info, len).map(|_| ())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{Authorize, Config, Pallet};
+use frame_support::{
+    sp_std::{marker::PhantomData, vec::Vec},
+    traits::Contains,
+};
+
+/// Map of the calls restricted to the role holders
+pub trait CallRoleMap<Call, RoleId> {
+    /// Roles allowed to dispatch the call, `None` if the call is not restricted
+    fn roles_for(call: &Call) -> Option<Vec<RoleId>>;
+}
+
+/// Filter of the calls by the roles of the dispatching account
+///
+/// A call is allowed if `Map` doesn't restrict it or the account holds any of its roles.
+/// `BaseCallFilter` doesn't know who dispatches the call, so the filter is checked against
+/// `(account, call)` pairs, e.g. in an origin filter:
+///
+/// ```ignore
+/// origin.add_filter(move |call| RoleCallFilter::<Runtime, CallRoles>::contains(&(who.clone(), call.clone())));
+/// ```
+pub struct RoleCallFilter<T, Map>(PhantomData<(T, Map)>);
+
+impl<T, Map> Contains<(T::AccountId, T::RuntimeCall)> for RoleCallFilter<T, Map>
+where
+    T: Config,
+    Map: CallRoleMap<T::RuntimeCall, T::RoleId>,
+{
+    fn contains((who, call): &(T::AccountId, T::RuntimeCall)) -> bool {
+        match Map::roles_for(call) {
+            Some(roles) => Pallet::<T>::authorize(who, roles.as_slice()),
+            None => true,
+        }
+    }
+}
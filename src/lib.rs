@@ -9,24 +9,108 @@
 //! ### Dispatchable functions
 //!
 //! * `grant_role` - grants a role to the user
+//! * `grant_role_from` - grants a role to the user that authorizes it only from the given block
+//! * `grant_role_windowed` - grants a role to the user that authorizes it only within the given blocks
+//! * `sponsor_and_grant` - grants a role to the user, keeping alive the user's account that has no providers while it holds the role
+//! * `create_roles_from_template` - creates a predefined bundle of linked roles, e.g. admin, editor and viewer
+//! * `force_preassign` - preassigns a role to the user by `ForceOrigin` without the grant authorization
+//! * `add_permission` - adds a permission to the role
+//! * `remove_permission` - removes a permission from the role
 //! * `revoke_role` - revokes a role from the user
+//! * `set_role_window` - limits the blocks during which the role is active
+//! * `grant_role_batch` - grants a role to a batch of users
+//! * `grant_roles` - grants several roles to the user, optionally skipping the conflicting ones
+//! * `revoke_role_batch` - revokes a role from a batch of users
+//! * `protect_role` - prevents the role from losing its last holder
+//! * `unprotect_role` - removes the protection from the role
+//! * `deny_role` - explicitly denies a role to the user, denial beats assignment
+//! * `undeny_role` - removes the denial of a role from the user
+//! * `purge_role` - removes the role with all of its assignments
+//! * `suspend_account` - suspends all roles of the user without revoking them
+//! * `reinstate_account` - reinstates the roles of the suspended user
+//! * `set_conflicts` - sets the roles that can't be held together with the role
+//! * `set_dependencies` - sets the roles the role depends on, revoking one of them revokes the role too
+//! * `set_self_assignable` - allows or forbids the role to grant itself
+//! * `remove_granter` - removes a granter from the role, removing the role itself forbids it to grant itself
+//! * `set_role_metadata` - sets a metadata entry of the role, e.g. its description
+//! * `set_role_uri` - sets the pointer to the off-chain metadata of the role, e.g. an IPFS CID
+//! * `transfer_role_ownership` - transfers the ownership of the role to another account
 //!
 //! ### Public functions
 //!
 //! * `add_role` - creates a new role
+//! * `add_owned_role` - creates a new role owned by an account that can manage it
+//! * `add_role_silent` - creates a new role without depositing an event, used by the genesis build
+//! * `preassign_granter` - preassigns a self-assignable role to the account that should grant it, used by the genesis build
 //! * `authorize` - challenges a user against the list of roles
+//! * `authorize_ordered` - challenges a user against the list of roles, checking the most likely ones first
+//! * `authorize_bounded` - challenges a user against the bounded list of roles
+//! * `authorize_mask` - challenges a user against each of up to 64 roles, returning the bitmask of the matched ones
+//! * `authorize_first_match` - challenges a user against a lazily evaluated iterator of roles, returning the first matched one
+//! * `authorize_expr` - challenges a user against a bounded and/or expression over roles, e.g. "(A and B) or C"
+//! * `authorize_name_prefix` - challenges a user against the roles whose names start with the prefix, also exposed by `RbacApi`, heavy and off-chain only
+//! * `authorize_permission` - challenges a user against the permission attached to the roles
 //! * `preassign_role` - assign user to the role prior to any block
+//! * `roles_held_among` - returns the roles from the list that user holds
+//! * `reconcile_plan` - returns the roles to grant and to revoke for the user to hold exactly the desired roles
+//! * `all_assignments` - returns all assignments of the active roles, also exposed by `RbacApi` runtime API, heavy and off-chain only
+//! * `roles_of` - returns the sorted active roles of the user, also exposed by `RbacApi`
+//! * `roles_of_paged` - returns a bounded page of the active roles of the user following a cursor, also exposed by `RbacApi`
+//! * `granted_at` - returns the block at which the role was granted to the user, also exposed by `RbacApi`
+//! * `roles_of_including_expired` - returns the sorted roles of the user including the ones whose window has ended, with the window end
+//! * `role_holders` - returns the sorted holders of the active role, also exposed by `RbacApi`, heavy and off-chain only
+//! * `role_hash` - returns the content hash of the role, also exposed by `RbacApi`
+//! * `membership_proof` - returns the proof of the assignment against `MembershipRoot`, also exposed by `RbacApi`, heavy and off-chain only
+//! * `authorize_many` - authorizes many users against the same role, also exposed by `RbacApi`
+//! * `role_metadata_entries` - returns all metadata entries of the role, also exposed by `RbacApi` as `role_metadata`
+//! * `roles_exist` - checks which of the given roles exist, also exposed by `RbacApi`
+//! * `grantable_by` - checks which of the given roles the granter can grant, also exposed by `RbacApi`
+//! * `add_roles_from_template` - creates the roles of a template and returns their ids, e.g. from `on_runtime_upgrade`
+//! * `audit_log` - returns the latest `AuditLogSize` grants and revocations from the oldest one
+//! * `reserve_id_range` - reserves a range of role ids for a subsystem
+//! * `add_role_in_range` - creates a new role with the id from the reserved range
+//! * `conflicting_role` - returns the role held by the user that conflicts with the role
+//! * `granter_count` - returns the number of the role granters
+//! * `authorize_calls`, `authorize_successes` - return the number of all and successful authorization checks
 //!
 //! ## Usage
 //!
 //! ### Prerequisites
 //!
-//! Import the traits from pallet to your pallet, create a type in config to initiate it from the runtime crate.
+//! Import the traits from pallet to your pallet, create a type in config to initiate it from the runtime crate. `RoleProvider` bundles `Authorize`, `AddRole` and `PreassignRole` into a single bound.
 //!
 //! ### Initialize roles
 //!
 //! You should initialize your roles from `on_runtime_upgrade` hook (if your chain is already running) or from `BuildGenesisConfig` trait implementation. Don't forget to save the `RoleId` that is returned from `add_role` call to use it later for challenges. Add some accounts to start giving out roles through `preassign_role` call.
 //!
+//! ### Role holder origin
+//!
+//! Instead of `ensure_signed` and a manual `authorize` call you can set `EnsureRoleHolder<Runtime, AdminRole>` as an `EnsureOrigin` in your pallet's config. It accepts signed origins of the `AdminRole` holders and `Origin::RoleHolder` origins made by `as_role_holder`.
+//!
+//! If a call takes a signed origin anyway, wrap its body with `role_gated!` instead. It checks the signer against the role of a `Get` getter through your `Authorize` provider and fails with the given error, e.g. your pallet's `NotAuthorized`.
+//!
+//! If the role depends on the call, e.g. each collection is guarded by its own role, set `EnsureRoleArg<Runtime>` as an `EnsureOriginWithArg` with the role id as the argument instead.
+//!
+//! ### Call filtering
+//!
+//! To restrict the calls of other pallets without changing them, implement `CallRoleMap` for the runtime calls and check `RoleCallFilter<Runtime, CallRoles>` against the dispatching account and the call, e.g. in an origin filter. Calls that are not in the map are always allowed. To reject such transactions already in the transaction pool, add `CheckRole<Runtime, CallRoles>` to the `SignedExtra` of your runtime.
+//!
+//! ### Genesis roles
+//!
+//! Roles and their holders can be also set in the pallet's `GenesisConfig`. After the build every role should have a holder, be self-assignable or be granted by another role that can be granted, e.g. viewer <- editor <- admin needs only a holder of admin, otherwise the genesis build panics, as nobody would be able to grant such a role. Accounts listed in `granters` get a self-assignable role preassigned, so they can grant it at runtime. Granters are still roles, so to let an account grant a role that is not self-assignable, preassign one of its granters.
+//!
+//! ### Brute-force protection
+//!
+//! Add `CheckGrantAttempts` to the `SignedExtra` of your runtime to count failed `grant_role` attempts. After `MaxFailedAttempts` failures the origin can't grant roles until `ResetWindow` blocks pass since its first failure. The attempts are counted from the result of the transaction, so wrappers hiding the error of the wrapped call, e.g. `utility.batch` or `proxy.proxy`, are not counted; keep the grant calls out of them with the runtime's `BaseCallFilter` if you rely on the lockout.
+//!
+//! ### Weights
+//!
+//! Set `type WeightInfo = pallet_rbac::SubstrateWeight<Runtime>` in production runtimes, so the storage accesses are charged with the `DbWeight` of your database. The `()` weights always charge them as RocksDb and are meant for tests.
+//!
+//! ### Logging
+//!
+//! Grants and revocations are logged at the debug level and authorization checks at the trace level under the `runtime::rbac` target, e.g. run the node with `-lruntime::rbac=trace` to troubleshoot them.
+//!
 //! ### Challenging against the role
 //!
 //! To challenge user against the role list you should use `authorize` public call. It will return a boolean value as the status of authorization.
@@ -51,9 +135,7 @@
 //! 	pub trait Config: frame_system::Config {
 //!         // other fields from config
 //!         type RoleId: MaxEncodedLen + Decode + EncodeLike + TypeInfo + Default + Clone;
-//!         type RBAC: rbac::Authorize<Self::AccountId, Self::RoleId>
-//! 			+ rbac::AddRole<Self::RoleId>
-//! 			+ rbac::PreassignRole<Self::AccountId, Self::RoleId>;
+//!         type RBAC: rbac::RoleProvider<Self::AccountId, Self::RoleId>;
 //!         type AdminAccount: Get<Self::AccountId>;
 //!     }
 //!
@@ -94,48 +176,356 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 pub use pallet::*;
 
+/// Target of the pallet's log messages, e.g. `RUST_LOG=runtime::rbac=trace`
+pub const LOG_TARGET: &str = "runtime::rbac";
+
 #[cfg(test)]
 mod mock;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod proptests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 pub mod traits;
 pub use traits::*;
 
 pub mod weights;
 pub use weights::*;
 
+pub mod extension;
+pub use extension::*;
+
+pub mod origin;
+pub use origin::*;
+
+pub mod filter;
+pub use filter::*;
+
+pub mod merkle;
+
+pub mod runtime_api;
+
+pub mod migrations;
+
+mod macros;
+#[doc(hidden)]
+pub use macros::__private;
+
+#[cfg(feature = "test-helpers")]
+pub mod test_helpers;
+
 #[frame_support::pallet]
 pub mod pallet {
     use core::fmt::Debug;
 
     use super::*;
     use codec::{Decode, EncodeLike, MaxEncodedLen};
+    use frame_support::sp_runtime::traits::{Hash, StaticLookup, TrailingZeroInput, Zero};
+    #[cfg(any(feature = "try-runtime", test))]
+    use frame_support::sp_runtime::TryRuntimeError;
     use frame_support::{
         pallet_prelude::{StorageDoubleMap, ValueQuery, *},
-        traits::Incrementable,
+        sp_std::{collections::btree_map::BTreeMap, vec::Vec},
     };
     use frame_system::pallet_prelude::*;
     use scale_info::TypeInfo;
 
     /// Struct representing the role metadata
+    ///
+    /// It is stored encoded in `Roles`, so changing its fields needs a migration,
+    /// e.g. `migrations::v1` converts the roles stored before the windows, conflicts and owners.
     #[derive(Clone, Debug, Encode, Decode, MaxEncodedLen, PartialEqNoBound, TypeInfo)]
-    #[scale_info(skip_type_params(LN, LG))]
-    pub struct RoleInfo<T: TypeInfo + Debug + PartialEq, LN: Get<u32>, LG: Get<u32>> {
+    #[scale_info(skip_type_params(LN, LG, LC))]
+    pub struct RoleInfo<
+        T: TypeInfo + Debug + PartialEq,
+        BN: TypeInfo + Debug + PartialEq,
+        LN: Get<u32>,
+        LG: Get<u32>,
+        LC: Get<u32>,
+        AId: TypeInfo + Debug + PartialEq,
+    > {
         /// Role name
         pub name: BoundedVec<u8, LN>,
         ///Role granters
         pub granters: BoundedVec<T, LG>,
+        /// First block at which the role is active, if any
+        pub active_from: Option<BN>,
+        /// Last block at which the role is active, if any
+        pub active_to: Option<BN>,
+        /// Roles that can't be held together with this one, kept symmetric by `set_conflicts`
+        pub conflicts: BoundedVec<T, LC>,
+        /// Block at which the role was created or changed the last time
+        pub modified_at: BN,
+        /// Account that can manage the role without holding any of its granters
+        pub owner: Option<AId>,
+    }
+
+    impl<T, BN, LN, LG, LC, AId> RoleInfo<T, BN, LN, LG, LC, AId>
+    where
+        T: TypeInfo + Debug + PartialEq + Clone,
+        BN: TypeInfo + Debug + PartialEq + Default,
+        LN: Get<u32>,
+        LG: Get<u32>,
+        LC: Get<u32>,
+        AId: TypeInfo + Debug + PartialEq,
+    {
+        /// Build a new role checking all of its invariants
+        ///
+        /// Duplicated granters are removed, `id` is added to the granters if `can_assign_itself` is set.
+        /// The role is created without conflicts and owner, `modified_at` should be set by the caller.
+        ///
+        /// **Parameters**:
+        /// - `id`: id of the new role
+        /// - `name`: slice of bytes representing the role name
+        /// - `granters`: slice of ids who can grant the role
+        /// - `can_assign_itself`: if set to true, then the role will be able to grant itself
+        /// - `role_exists`: check that a granter role exists
+        ///
+        /// **Errors**:
+        /// - `NameTooLong` if the name is longer than `LN`
+        /// - `GrantersListTooLong` if there are more granters than `LG`
+        /// - `RoleNotExist` if one of the granters doesn't exist
+        pub fn try_new(
+            id: T,
+            name: &[u8],
+            granters: &[T],
+            can_assign_itself: bool,
+            role_exists: impl Fn(&T) -> bool,
+        ) -> Result<Self, InterfaceError<T>> {
+            let name = name
+                .to_vec()
+                .try_into()
+                .map_err(|_| InterfaceError::NameTooLong {
+                    expected: LN::get(),
+                    observed: name.len(),
+                })?;
+
+            let mut unique_granters: Vec<T> = Vec::with_capacity(granters.len() + 1);
+            let self_granter = can_assign_itself.then_some(&id);
+            for granter in granters.iter().chain(self_granter) {
+                if !unique_granters.contains(granter) {
+                    unique_granters.push(granter.clone());
+                }
+            }
+            let observed = unique_granters.len();
+            let granters: BoundedVec<T, LG> =
+                unique_granters
+                    .try_into()
+                    .map_err(|_| InterfaceError::GrantersListTooLong {
+                        expected: LG::get(),
+                        observed,
+                    })?;
+
+            if let Some(granter) = granters
+                .iter()
+                .find(|granter| **granter != id && !role_exists(granter))
+            {
+                Err(InterfaceError::RoleNotExist {
+                    role: granter.clone(),
+                })?
+            }
+
+            Ok(RoleInfo {
+                name,
+                granters,
+                active_from: None,
+                active_to: None,
+                conflicts: BoundedVec::default(),
+                modified_at: BN::default(),
+                owner: None,
+            })
+        }
+    }
+
+    impl<T, BN, LN, LG, LC, AId> RoleInfo<T, BN, LN, LG, LC, AId>
+    where
+        T: TypeInfo + Debug + PartialEq,
+        BN: TypeInfo + Debug + PartialEq,
+        LN: Get<u32>,
+        LG: Get<u32>,
+        LC: Get<u32>,
+        AId: TypeInfo + Debug + PartialEq,
+        Self: Encode,
+    {
+        /// Hash of the SCALE encoded role, changes whenever any of its fields changes
+        pub fn content_hash<H: Hash>(&self) -> H::Output {
+            H::hash_of(self)
+        }
+    }
+
+    /// Action recorded in the audit log
+    #[derive(Clone, Copy, Debug, Decode, Encode, Eq, MaxEncodedLen, PartialEq, TypeInfo)]
+    pub enum AuditAction {
+        /// Role was granted
+        Grant,
+        /// Role was revoked
+        Revoke,
+    }
+
+    /// Predefined bundle of linked roles created by `create_roles_from_template`
+    #[derive(Clone, Copy, Debug, Decode, Encode, Eq, MaxEncodedLen, PartialEq, TypeInfo)]
+    pub enum RoleTemplate {
+        /// `admin` granting itself and `editor`, `editor` granting `viewer`
+        AdminEditorViewer,
     }
 
+    /// Entry of the audit log
+    #[derive(Clone, Debug, Decode, Encode, Eq, MaxEncodedLen, PartialEq, TypeInfo)]
+    pub struct AuditEntry<AId, RId, BN> {
+        /// Block of the action
+        pub block: BN,
+        /// Account that granted or revoked the role
+        pub actor: AId,
+        /// Account the role was granted to or revoked from
+        pub user: AId,
+        /// Granted or revoked role
+        pub role_id: RId,
+        /// Recorded action
+        pub action: AuditAction,
+    }
+
+    /// Assignment of the role to the user
+    #[derive(Clone, Copy, Debug, Decode, Encode, Eq, MaxEncodedLen, PartialEq, TypeInfo)]
+    pub struct AssignmentInfo<BN> {
+        /// Block at which the role was granted or preassigned, e.g. zero for the genesis assignments.
+        /// Granting a held role again keeps the original block.
+        /// It is zero for the assignments migrated from `bool` flags.
+        pub granted_at: BN,
+        /// Index of the extrinsic that granted the role within the `granted_at` block.
+        /// It is `None` if the role wasn't granted by an extrinsic, e.g. at genesis or in a hook,
+        /// and for the assignments made before the storage version 3.
+        pub extrinsic_index: Option<u32>,
+    }
+
+    /// Assignment as it is stored by the pallet
+    pub type AssignmentInfoOf<T> = AssignmentInfo<BlockNumberFor<T>>;
+
+    /// Audit log entry as it is stored by the pallet
+    pub type AuditEntryOf<T> = AuditEntry<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::RoleId,
+        BlockNumberFor<T>,
+    >;
+
+    /// Role metadata as it is stored by the pallet
+    pub type RoleInfoOf<T> = RoleInfo<
+        <T as Config>::RoleId,
+        BlockNumberFor<T>,
+        <T as Config>::NameMaxLength,
+        <T as Config>::GrantersListMaxLength,
+        <T as Config>::MaxConflicts,
+        <T as frame_system::Config>::AccountId,
+    >;
+
+    /// Key of the role metadata entry
+    pub type MetadataKeyOf<T> = BoundedVec<u8, <T as Config>::MaxMetadataKeyLength>;
+
+    /// Value of the role metadata entry
+    pub type MetadataValueOf<T> = BoundedVec<u8, <T as Config>::MaxMetadataValueLength>;
+
+    /// Pointer to the off-chain metadata of the role, e.g. an IPFS CID
+    pub type RoleUriOf<T> = BoundedVec<u8, <T as Config>::UriMaxLength>;
+
+    /// Source of the account id that is resolved by the runtime's `Lookup`
+    pub type AccountIdLookupOf<T> = <<T as frame_system::Config>::Lookup as StaticLookup>::Source;
+
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(8);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
-    /// Storage for account-role relationship
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+            // the assignments changed in this block are not known yet, so the largest tree is charged
+            if T::TrackMembershipRoot::get() {
+                T::WeightInfo::update_membership_root(T::MaxMembershipSize::get())
+            } else {
+                Weight::zero()
+            }
+        }
+
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::reap_expired_assignments(remaining_weight)
+        }
+
+        fn on_finalize(_n: BlockNumberFor<T>) {
+            if T::TrackMembershipRoot::get() && MembershipDirty::<T>::take() {
+                Self::update_membership_root();
+            }
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), TryRuntimeError> {
+            Self::do_try_state()
+        }
+    }
+
+    /// Storage for account-role relationship, `None` if the role is not assigned to the user
     #[pallet::storage]
-    #[pallet::getter(fn assignments)]
+    #[pallet::getter(fn assignment_info)]
     pub type Assignments<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        AssignmentInfoOf<T>,
+    >;
+
+    /// Storage for the first block at which the assignment authorizes the user, if it was scheduled
+    #[pallet::storage]
+    #[pallet::getter(fn assignment_active_from)]
+    pub type AssignmentActiveFrom<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        BlockNumberFor<T>,
+    >;
+
+    /// Storage for the last block at which the assignment authorizes the user, if it was limited
+    #[pallet::storage]
+    #[pallet::getter(fn assignment_active_to)]
+    pub type AssignmentActiveTo<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        BlockNumberFor<T>,
+    >;
+
+    /// Storage for the last assignment window visited by the cleanup in `on_idle`,
+    /// the next cleanup resumes after it. `None` starts from the beginning.
+    #[pallet::storage]
+    pub type ReapCursor<T: Config> = StorageValue<_, (T::AccountId, T::RoleId)>;
+
+    /// Storage for the block at which the role was revoked from the user, kept only while `FlapCooldown` is set
+    #[pallet::storage]
+    #[pallet::getter(fn last_revoked)]
+    pub type LastRevoked<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        BlockNumberFor<T>,
+    >;
+
+    /// Storage for roles that are explicitly denied to the user.
+    /// Denial takes precedence over assignment: a denied role fails the whole authorization.
+    #[pallet::storage]
+    #[pallet::getter(fn denials)]
+    pub type Denials<T: Config> = StorageDoubleMap<
         _,
         Blake2_128Concat,
         T::AccountId,
@@ -145,19 +535,195 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Storage for the accounts that have an assignment, a denial or a revocation of the role.
+    /// It indexes `Assignments`, `Denials` and `LastRevoked` by role, so `purge_role` doesn't scan them.
+    #[pallet::storage]
+    pub type RoleAccounts<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::RoleId, Blake2_128Concat, T::AccountId, ()>;
+
+    /// Storage for the sponsors of the accounts given a provider reference by `sponsor_and_grant`,
+    /// by the sponsored role. The reference is released when the role is removed from the user.
+    #[pallet::storage]
+    #[pallet::getter(fn sponsored)]
+    pub type Sponsored<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        T::AccountId,
+    >;
+
+    /// Storage for suspended accounts. Suspended accounts keep their roles but can't be authorized by them.
+    #[pallet::storage]
+    #[pallet::getter(fn suspended_accounts)]
+    pub type SuspendedAccounts<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+    /// Storage for the account that granted the role to the user.
+    /// Roles preassigned by other pallets have no granter.
+    #[pallet::storage]
+    #[pallet::getter(fn assignment_granter)]
+    pub type AssignmentGranter<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::RoleId,
+        T::AccountId,
+    >;
+
+    /// Storage for the number of users holding the role
+    #[pallet::storage]
+    #[pallet::getter(fn holder_count)]
+    pub type HolderCount<T: Config> = StorageMap<_, Blake2_128Concat, T::RoleId, u32, ValueQuery>;
+
+    /// Storage for the number of roles held by the user
+    #[pallet::storage]
+    #[pallet::getter(fn account_role_count)]
+    pub type AccountRoleCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Storage for roles that can't lose their last holder
+    #[pallet::storage]
+    #[pallet::getter(fn protected_roles)]
+    pub type ProtectedRoles<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::RoleId, bool, ValueQuery>;
+
+    /// Storage for failed grant attempts: the block of the first failure in the window and the number of failures
+    #[pallet::storage]
+    #[pallet::getter(fn failed_grant_attempts)]
+    pub type FailedGrantAttempts<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (BlockNumberFor<T>, u32)>;
+
     /// Storage for role information
     #[pallet::storage]
     #[pallet::getter(fn roles)]
-    pub type Roles<T: Config> = StorageMap<
+    pub type Roles<T: Config> = StorageMap<_, Blake2_128Concat, T::RoleId, RoleInfoOf<T>>;
+
+    /// Storage for role ids by their names. Several roles can have the same name.
+    #[pallet::storage]
+    pub type RoleNameIndex<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        BoundedVec<u8, T::NameMaxLength>,
+        Blake2_128Concat,
+        T::RoleId,
+        (),
+    >;
+
+    /// Storage for the permissions granted by the role
+    #[pallet::storage]
+    #[pallet::getter(fn role_permissions)]
+    pub type RolePermissions<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::RoleId,
+        BoundedVec<T::PermissionId, T::MaxPermissionsPerRole>,
+        ValueQuery,
+    >;
+
+    /// Storage for the roles the role depends on: holding it requires holding each of them
+    #[pallet::storage]
+    #[pallet::getter(fn role_dependencies)]
+    pub type RoleDependencies<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::RoleId,
+        BoundedVec<T::RoleId, T::MaxDependencies>,
+        ValueQuery,
+    >;
+
+    /// Storage for the roles depending on the role, the reverse of `RoleDependencies`
+    #[pallet::storage]
+    #[pallet::getter(fn role_dependents)]
+    pub type RoleDependents<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::RoleId,
+        BoundedVec<T::RoleId, T::MaxDependencies>,
+        ValueQuery,
+    >;
+
+    /// Storage for the metadata of the role, e.g. its description or rank
+    #[pallet::storage]
+    #[pallet::getter(fn role_metadata)]
+    pub type RoleMetadata<T: Config> = StorageDoubleMap<
         _,
         Blake2_128Concat,
         T::RoleId,
-        RoleInfo<T::RoleId, T::NameMaxLength, T::GrantersListMaxLength>,
+        Blake2_128Concat,
+        MetadataKeyOf<T>,
+        MetadataValueOf<T>,
     >;
 
+    /// Storage for the number of the role metadata entries
+    #[pallet::storage]
+    #[pallet::getter(fn role_metadata_count)]
+    pub type RoleMetadataCount<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::RoleId, u32, ValueQuery>;
+
+    /// Storage for the pointers to the off-chain metadata of the roles, kept apart to keep `RoleInfo` lean
+    #[pallet::storage]
+    #[pallet::getter(fn role_uri)]
+    pub type RoleUri<T: Config> = StorageMap<_, Blake2_128Concat, T::RoleId, RoleUriOf<T>>;
+
+    /// Storage for the reserved id ranges: start of the range to its inclusive end and the next id to allocate
+    #[pallet::storage]
+    #[pallet::getter(fn id_ranges)]
+    pub type IdRanges<T: Config> = StorageMap<_, Blake2_128Concat, u128, (u128, u128)>;
+
+    /// Storage for the reserved id ranges as role ids: first id of the range to its last id and the number of it.
+    /// `add_role` skips the ranges through it, as the generated ids can't be compared with `IdRanges`.
+    #[pallet::storage]
+    pub type ReservedIds<T: Config> = StorageMap<_, Blake2_128Concat, T::RoleId, (T::RoleId, u128)>;
+
     /// Storage with the latest role id. Used for ensure that there won't be collisions with role generation.
+    /// It is `RoleIdStart` until the first id is generated.
+    #[pallet::storage]
+    type IdGenerator<T: Config> = StorageValue<_, T::RoleId, ValueQuery, T::RoleIdStart>;
+
+    /// Storage with the number of generated role ids. Used by the id allocators that don't rely on the previous id.
+    #[pallet::storage]
+    type IdCounter<T: Config> = StorageValue<_, u128, ValueQuery>;
+
+    /// Storage for the ids of the purged roles, reused by `add_role` if `RecycleRoleIds` is set.
+    /// The last freed id is reused first.
+    #[pallet::storage]
+    #[pallet::getter(fn freed_ids)]
+    pub type FreedIds<T: Config> =
+        StorageValue<_, BoundedVec<T::RoleId, T::MaxFreedIds>, ValueQuery>;
+
+    /// Storage for the last `AuditLogSize` grants and revocations, indexed by their slot in the ring buffer
+    #[pallet::storage]
+    pub type AuditLog<T: Config> = StorageMap<_, Twox64Concat, u32, AuditEntryOf<T>>;
+
+    /// Storage with the slot of the audit log that is written next, it holds the oldest entry once the log is full
+    #[pallet::storage]
+    pub type AuditLogHead<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage with the number of authorization checks, tracked if `TrackAuthorizeMetrics` is set
+    #[pallet::storage]
+    #[pallet::getter(fn authorize_calls)]
+    pub type AuthorizeCalls<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Storage with the number of successful authorization checks, tracked if `TrackAuthorizeMetrics` is set
+    #[pallet::storage]
+    #[pallet::getter(fn authorize_successes)]
+    pub type AuthorizeSuccesses<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// Storage with the Merkle root of all assignments, tracked if `TrackMembershipRoot` is set
+    #[pallet::storage]
+    #[pallet::getter(fn membership_root)]
+    pub type MembershipRoot<T: Config> = StorageValue<_, T::Hash, ValueQuery>;
+
+    /// Storage with the number of the assignments under `MembershipRoot`
+    #[pallet::storage]
+    pub type MembershipSize<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage flag set when the assignments change, `MembershipRoot` is recomputed at the end of the block
     #[pallet::storage]
-    type IdGenerator<T: Config> = StorageValue<_, T::RoleId, ValueQuery>;
+    pub type MembershipDirty<T: Config> = StorageValue<_, bool, ValueQuery>;
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
@@ -175,31 +741,259 @@ pub mod pallet {
             + EncodeLike
             + Eq
             + MaxEncodedLen
-            + TypeInfo
-            + Incrementable;
+            + MaybeSerializeDeserialize
+            + Ord
+            + TypeInfo;
+        /// Type used for role id generation.
+        /// Use `IncrementableIds` if `RoleId` implements `Incrementable` and `SequentialIds` otherwise.
+        type IdAllocator: AllocateRoleId<Self::RoleId>;
+        /// Role id the generation starts from, the first generated id follows it.
+        /// Use `GetDefault` to start from the default id. `SequentialIds` don't depend on it.
+        type RoleIdStart: Get<Self::RoleId>;
+        /// Reuse the ids of the purged roles before generating new ones, e.g. for small id types.
+        /// An id is freed only when the purge is complete, so no assignment, denial or metadata
+        /// of the old role can match the new one. Ids of the reserved ranges are not reused by `add_role`.
+        #[pallet::constant]
+        type RecycleRoleIds: Get<bool>;
+        /// Maximum number of the freed ids kept for recycling, the ids freed above it are not reused
+        #[pallet::constant]
+        type MaxFreedIds: Get<u32>;
         /// Maximum length of role name
+        #[pallet::constant]
         type NameMaxLength: Get<u32> + Clone + Debug;
         /// Maximum length of granters list
+        #[pallet::constant]
         type GrantersListMaxLength: Get<u32> + Clone + Debug;
+        /// Maximum number of users in a batch call
+        #[pallet::constant]
+        type BatchMaxLength: Get<u32> + Clone + Debug;
+        /// Origin that can protect roles from losing their last holder
+        type ProtectOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Number of failed grant attempts after which the origin can't grant roles
+        #[pallet::constant]
+        type MaxFailedAttempts: Get<u32>;
+        /// Number of blocks after the first failed grant attempt when the attempts are reset
+        #[pallet::constant]
+        type ResetWindow: Get<BlockNumberFor<Self>>;
+        /// Number of blocks after the revocation during which the role can't be granted to the user again,
+        /// zero disables the cooldown
+        #[pallet::constant]
+        type FlapCooldown: Get<BlockNumberFor<Self>>;
+        /// Origin that can purge roles and set their conflicts
+        type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Maximum number of storage items removed by a single `purge_role` call
+        #[pallet::constant]
+        type MaxPurge: Get<u32>;
+        /// Handler notified about the removed roles, `()` if nobody needs it
+        type OnRoleChanged: OnRoleChanged<Self::RoleId>;
+        /// Maximum number of roles matched by a name prefix
+        #[pallet::constant]
+        type MaxPrefixMatches: Get<u32>;
+        /// Origin that can suspend accounts
+        type SuspendOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Maximum number of roles conflicting with a role
+        #[pallet::constant]
+        type MaxConflicts: Get<u32> + Clone + Debug;
+        /// Maximum number of roles the role depends on and of roles depending on it
+        #[pallet::constant]
+        type MaxDependencies: Get<u32> + Clone + Debug;
+        /// Reject granting roles to the zero account, use `ConstBool<false>` to allow it
+        #[pallet::constant]
+        type RejectDefaultAccount: Get<bool>;
+        /// Result of `authorize` against an empty role list: `true` treats it as no restriction,
+        /// `false` denies it. Suspended accounts get the same result, as no role is checked.
+        /// Roles without granters can't be granted with either setting.
+        #[pallet::constant]
+        type EmptyRolesAllow: Get<bool>;
+        /// Count authorization checks in `AuthorizeCalls` and `AuthorizeSuccesses`.
+        /// It adds two storage writes to every check, including the ones made by the pallet calls.
+        #[pallet::constant]
+        type TrackAuthorizeMetrics: Get<bool>;
+        /// Recompute `MembershipRoot` at the end of every block changing the assignments.
+        /// It iterates all assignments, so enable it only if their number is moderate.
+        #[pallet::constant]
+        type TrackMembershipRoot: Get<bool>;
+        /// Maximum number of the assignments under `MembershipRoot`, every block is charged for a tree of this size.
+        /// If there are more assignments, the root is reset to the default hash and no proofs are built.
+        #[pallet::constant]
+        type MaxMembershipSize: Get<u32>;
+        /// Maximum number of roles held by an account
+        #[pallet::constant]
+        type MaxRolesPerAccount: Get<u32>;
+        /// Number of the latest grants and revocations kept in the audit log, `0` disables it
+        #[pallet::constant]
+        type AuditLogSize: Get<u32>;
+        /// Type used for identification of the actions gated by roles
+        type PermissionId: Clone
+            + Copy
+            + Debug
+            + Decode
+            + EncodeLike
+            + Eq
+            + MaxEncodedLen
+            + TypeInfo;
+        /// Maximum number of permissions of a role
+        #[pallet::constant]
+        type MaxPermissionsPerRole: Get<u32>;
+        /// Maximum length of the role metadata key
+        #[pallet::constant]
+        type MaxMetadataKeyLength: Get<u32>;
+        /// Maximum length of the role metadata value
+        #[pallet::constant]
+        type MaxMetadataValueLength: Get<u32>;
+        /// Maximum number of the metadata entries of a role
+        #[pallet::constant]
+        type MaxMetadataPerRole: Get<u32>;
+        /// Maximum length of the pointer to the off-chain metadata of a role
+        #[pallet::constant]
+        type UriMaxLength: Get<u32>;
     }
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
         /// Role was created
-        RoleCreated {
-            id: T::RoleId,
-            info: RoleInfo<T::RoleId, T::NameMaxLength, T::GrantersListMaxLength>,
-        },
-        /// Role was granted to the user
+        RoleCreated { id: T::RoleId, info: RoleInfoOf<T> },
+        /// Role was granted to the user, `newly_granted` is false if the user already held it.
+        /// `total_holders` is the number of the role holders after the grant.
         RoleGranted {
             user: T::AccountId,
             role_id: T::RoleId,
+            newly_granted: bool,
+            total_holders: u32,
+        },
+        /// Role granted to the user authorizes it only from `active_from` block
+        RoleGrantScheduled {
+            user: T::AccountId,
+            role_id: T::RoleId,
+            active_from: BlockNumberFor<T>,
+        },
+        /// Role granted to the user authorizes it only from `active_from` to `active_to` block inclusive
+        RoleGrantWindowed {
+            user: T::AccountId,
+            role_id: T::RoleId,
+            active_from: BlockNumberFor<T>,
+            active_to: BlockNumberFor<T>,
         },
-        /// Role was revoked from the user
+        /// Role was revoked from the user.
+        /// `total_holders` is the number of the role holders after the revocation.
         RoleRevoked {
             user: T::AccountId,
             role_id: T::RoleId,
+            total_holders: u32,
+        },
+        /// Role was granted to a batch of users
+        BatchGrantCompleted {
+            role_id: T::RoleId,
+            granted: u32,
+            skipped: u32,
+        },
+        /// Role was revoked from a batch of users
+        BatchRevokeCompleted {
+            role_id: T::RoleId,
+            revoked: u32,
+            skipped: u32,
+        },
+        /// Role was denied to the user
+        RoleDenied {
+            user: T::AccountId,
+            role_id: T::RoleId,
+        },
+        /// Denial of the role was removed from the user
+        RoleUndenied {
+            user: T::AccountId,
+            role_id: T::RoleId,
+        },
+        /// Role was protected from losing its last holder
+        RoleProtected { role_id: T::RoleId },
+        /// Role is not protected anymore
+        RoleUnprotected { role_id: T::RoleId },
+        /// Role was purged, `complete` is false if there are items left to be removed by the next call
+        RolePurged {
+            role_id: T::RoleId,
+            removed: u32,
+            complete: bool,
+        },
+        /// All roles of the account were suspended
+        AccountSuspended { user: T::AccountId },
+        /// Roles of the account were reinstated
+        AccountReinstated { user: T::AccountId },
+        /// Active window of the role was changed
+        RoleWindowSet {
+            role_id: T::RoleId,
+            active_from: Option<BlockNumberFor<T>>,
+            active_to: Option<BlockNumberFor<T>>,
+        },
+        /// Ownership of the role was transferred
+        RoleOwnershipTransferred {
+            role_id: T::RoleId,
+            old_owner: T::AccountId,
+            new_owner: T::AccountId,
+        },
+        /// Role can grant itself now if `enabled` is set, or can't otherwise
+        SelfAssignableChanged { role_id: T::RoleId, enabled: bool },
+        /// Permission was added to the role
+        PermissionAdded {
+            role_id: T::RoleId,
+            permission: T::PermissionId,
+        },
+        /// Permission was removed from the role
+        PermissionRemoved {
+            role_id: T::RoleId,
+            permission: T::PermissionId,
+        },
+        /// Conflicts of the role were set, each of `conflicts` now conflicts with the role too
+        ConflictsSet {
+            role_id: T::RoleId,
+            conflicts: BoundedVec<T::RoleId, T::MaxConflicts>,
+        },
+        /// Roles the role depends on were set
+        DependenciesSet {
+            role_id: T::RoleId,
+            dependencies: BoundedVec<T::RoleId, T::MaxDependencies>,
+        },
+        /// Role can't be granted by the holders of `granter` anymore
+        GranterRemoved {
+            role_id: T::RoleId,
+            granter: T::RoleId,
+        },
+        /// Metadata entry of the role was set
+        RoleMetadataSet {
+            role_id: T::RoleId,
+            key: MetadataKeyOf<T>,
+            value: MetadataValueOf<T>,
+        },
+        /// Roles of the template were created, in the order of the template with its top role first
+        RolesCreatedFromTemplate {
+            template: RoleTemplate,
+            role_ids: Vec<T::RoleId>,
+            admin: T::AccountId,
+        },
+        /// Some of the roles weren't granted to the user by `grant_roles`, as they conflict with the held ones
+        GrantPartial {
+            user: T::AccountId,
+            granted: Vec<T::RoleId>,
+            skipped_conflicts: Vec<T::RoleId>,
+        },
+        /// Pointer to the off-chain metadata of the role was set, empty if it was removed
+        RoleUriSet {
+            role_id: T::RoleId,
+            uri: RoleUriOf<T>,
+        },
+        /// Account without providers was kept alive by the sponsor granting it a role
+        AccountSponsored {
+            user: T::AccountId,
+            sponsor: T::AccountId,
+        },
+        /// Assignment whose window has ended was removed by the cleanup in `on_idle`
+        AssignmentExpired {
+            user: T::AccountId,
+            role_id: T::RoleId,
+        },
+        /// Role was preassigned to the user by `ForceOrigin`
+        RolePreassigned {
+            user: T::AccountId,
+            role_id: T::RoleId,
         },
     }
 
@@ -207,151 +1001,2571 @@ pub mod pallet {
     pub enum Error<T> {
         /// User is not authorized for such action
         NotAuthorized,
+        /// User is not authorized to grant the role
+        NotAuthorizedToGrant,
+        /// User is not authorized to revoke the role
+        NotAuthorizedToRevoke,
         /// No such role exists
         RoleNotExist,
+        /// Role is not granted to the user
+        RoleNotGranted,
+        /// Active window ends before it starts
+        InvalidWindow,
+        /// Role is protected and this user is its last holder
+        CannotRemoveLastHolderOfProtectedRole,
+        /// Origin has failed to grant roles too many times, it should wait for the window to reset
+        TooManyFailedAttempts,
+        /// User holds a role conflicting with the granted one, use `conflicting_role` to find it.
+        /// The role id is not a part of the error, as it may not fit into the module error.
+        ConflictingRole,
+        /// Role can't conflict with itself
+        InvalidConflict,
+        /// One of the conflicting roles already has `MaxConflicts` conflicts
+        TooManyConflicts,
+        /// Role can't depend on itself
+        InvalidDependency,
+        /// One of the dependencies already has `MaxDependencies` dependent roles
+        TooManyDependents,
+        /// Roles can't be granted to the zero account
+        InvalidGrantee,
+        /// Role already has `GrantersListMaxLength` granters
+        TooManyGranters,
+        /// Granter is the only granter of the role, nobody would be able to grant it without it
+        CannotRemoveLastGranter,
+        /// User already holds `MaxRolesPerAccount` roles
+        TooManyRolesForAccount,
+        /// Role already has `MaxPermissionsPerRole` permissions
+        TooManyPermissions,
+        /// Role was revoked from the user less than `FlapCooldown` blocks ago
+        Flapping,
+        /// Role is not one of the role granters
+        GranterNotFound,
+        /// Role already has `MaxMetadataPerRole` metadata entries
+        TooManyMetadataEntries,
+        /// Role name is longer than `NameMaxLength`
+        NameTooLong,
+        /// Pointer to the off-chain metadata is longer than `UriMaxLength`
+        UriTooLong,
+        /// There are no role ids left to create a role
+        IdsExhausted,
+        /// Role with its holders and metadata entries are more than `MaxPurge` items
+        TooManyToPurge,
     }
 
-    #[pallet::call]
-    impl<T: Config> Pallet<T> {
-        /// Grant a role to the user
-        ///
-        /// Parameters:
-        /// - `origin`: role granter.
-        /// - `user`: role grantee.
+    /// Origin of an account that holds the role
+    #[pallet::origin]
+    #[derive(
+        CloneNoBound,
+        Decode,
+        Encode,
+        EqNoBound,
+        MaxEncodedLen,
+        PartialEqNoBound,
+        RuntimeDebugNoBound,
+        TypeInfo,
+    )]
+    #[scale_info(skip_type_params(T))]
+    pub enum Origin<T: Config> {
+        /// Signed by the account holding the role
+        RoleHolder(T::AccountId, T::RoleId),
+    }
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// Roles to create: name, granters and whether the role can grant itself.
+        /// Ids are generated in the order of this list.
+        pub roles: Vec<(Vec<u8>, Vec<T::RoleId>, bool)>,
+        /// Roles to preassign to the accounts
+        pub assignments: Vec<(T::AccountId, T::RoleId)>,
+        /// Self-assignable roles to preassign to the accounts that should grant them,
+        /// see `preassign_granter`
+        pub granters: Vec<(T::AccountId, T::RoleId)>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            for (name, granters, can_assign_itself) in self.roles.iter() {
+                Pallet::<T>::add_role_silent(name, granters, *can_assign_itself)
+                    .expect("genesis role should be valid");
+            }
+            for (user, role_id) in self.assignments.iter() {
+                Pallet::<T>::preassign_role(user.clone(), *role_id)
+                    .expect("genesis assignment should refer to an existing role");
+            }
+            for (account, role_id) in self.granters.iter() {
+                Pallet::<T>::preassign_granter(account.clone(), *role_id)
+                    .expect("genesis granter should refer to an existing self-assignable role");
+            }
+            Pallet::<T>::ensure_roles_grantable();
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Grant a role to the user
+        ///
+        /// Failed attempts are counted by `CheckGrantAttempts` signed extension on a best-effort basis,
+        /// after `MaxFailedAttempts` of them the origin can't grant roles until `ResetWindow` passes.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
         /// - `role_id`: id of role to grant.
         ///
         /// Events:
-        /// - `RoleGranted(user, role_id)` if role is granted
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` if role is granted
         ///
         /// Errors:
-        /// - `NotAuthorized` if `origin` is not authorized to grant this role
+        /// - `LookupError` if `user` can't be resolved
+        /// - `NotAuthorizedToGrant` if `origin` is not authorized to grant this role
         /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `TooManyFailedAttempts` if `origin` has failed to grant roles too many times
+        /// - `ConflictingRole` if `user` holds a role conflicting with this one
+        /// - `InvalidGrantee` if `user` is the zero account and `RejectDefaultAccount` is set
+        /// - `TooManyRolesForAccount` if `user` already holds `MaxRolesPerAccount` roles
+        /// - `Flapping` if the role was revoked from `user` less than `FlapCooldown` blocks ago
         ///
         /// Complexity:
-        ///  - O(1)
+        ///  - O(G + C) where G is the number of the role granters and C is the number of its conflicts
         #[pallet::call_index(0)]
-        #[pallet::weight(T::WeightInfo::grant_role())]
+        #[pallet::weight(T::WeightInfo::grant_role(T::GrantersListMaxLength::get()))]
         pub fn grant_role(
             origin: OriginFor<T>,
-            user: T::AccountId,
+            user: AccountIdLookupOf<T>,
             role_id: T::RoleId,
-        ) -> DispatchResult {
+        ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
-            let role = Roles::<T>::get(role_id);
+            let user = T::Lookup::lookup(user)?;
 
-            let Some(role) = role else {
-                Err(Error::<T>::RoleNotExist)?
+            let granters = Self::do_grant(who, &user, role_id)?;
+            AssignmentActiveFrom::<T>::remove(&user, role_id);
+            AssignmentActiveTo::<T>::remove(&user, role_id);
+
+            Ok(Some(T::WeightInfo::grant_role(granters)).into())
+        }
+
+        /// Revoke a role from the user
+        ///
+        /// The account that granted the role to the user can always revoke it,
+        /// even if it is not a granter of the role anymore.
+        /// The last holder of a protected role can't be revoked.
+        /// The roles depending on the revoked one are revoked from the user as well,
+        /// only the direct dependents are revoked.
+        ///
+        /// Parameters:
+        /// - `origin`: role revoker.
+        /// - `user`: account to revoke a role from, resolved by the runtime's `Lookup`.
+        /// - `role_id`: id of role to revoke.
+        ///
+        /// Events:
+        /// - `RoleRevoked(user, role_id, total_holders)` if role is revoked and for each revoked dependent role
+        ///
+        /// Errors:
+        /// - `LookupError` if `user` can't be resolved
+        /// - `NotAuthorizedToRevoke` if `origin` is not authorized to revoke this role
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `RoleNotGranted` if `user` doesn't hold the role, so nothing is revoked and no event is emitted
+        /// - `CannotRemoveLastHolderOfProtectedRole` if the role or one of its dependents is protected
+        ///   and `user` is its last holder
+        ///
+        /// Complexity:
+        ///  - O(G + D) where G is the number of the role granters, zero for the original granter,
+        ///    and D is the number of the roles depending on it
+        #[pallet::call_index(1)]
+        #[pallet::weight(
+            T::WeightInfo::revoke_role(T::GrantersListMaxLength::get())
+                .saturating_add(T::WeightInfo::revoke_dependents(T::MaxDependencies::get()))
+        )]
+        pub fn revoke_role(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_id: T::RoleId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let user = T::Lookup::lookup(user)?;
+            // the original granter is not authorized against the granters, so none of them is charged
+            let granters = if AssignmentGranter::<T>::get(&user, role_id).as_ref() != Some(&who) {
+                let role = Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorizedToRevoke)?;
+                role.granters.len() as u32
+            } else if !Roles::<T>::contains_key(role_id) {
+                Err(Self::role_not_exist(role_id))?
+            } else {
+                0
             };
+            if !Assignments::<T>::contains_key(&user, role_id) {
+                Err(Error::<T>::RoleNotGranted)?
+            }
 
-            if !Pallet::<T>::authorize(&who, role.granters.as_slice()) {
-                Err(Error::<T>::NotAuthorized)?
+            Self::revoke_as(&who, &user, role_id)?;
+
+            Self::deposit_event(Event::RoleRevoked {
+                user: user.clone(),
+                role_id,
+                total_holders: HolderCount::<T>::get(role_id),
+            });
+
+            let dependents = RoleDependents::<T>::get(role_id);
+            for dependent in dependents.iter() {
+                if Assignments::<T>::contains_key(&user, dependent) {
+                    Self::revoke_as(&who, &user, *dependent)?;
+                    Self::deposit_event(Event::RoleRevoked {
+                        user: user.clone(),
+                        role_id: *dependent,
+                        total_holders: HolderCount::<T>::get(dependent),
+                    });
+                }
+            }
+
+            Ok(Some(
+                T::WeightInfo::revoke_role(granters)
+                    .saturating_add(T::WeightInfo::revoke_dependents(dependents.len() as u32)),
+            )
+            .into())
+        }
+
+        /// Set the blocks during which the role is active
+        ///
+        /// Users holding the role are not authorized by it outside of this window.
+        /// Both bounds are inclusive, `None` leaves the corresponding side open.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `active_from`: first block at which the role is active.
+        /// - `active_to`: last block at which the role is active.
+        ///
+        /// Events:
+        /// - `RoleWindowSet(role_id, active_from, active_to)` if window is changed
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `InvalidWindow` if `active_to` is lower than `active_from`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::set_role_window())]
+        pub fn set_role_window(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            active_from: Option<BlockNumberFor<T>>,
+            active_to: Option<BlockNumberFor<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let mut role = Self::ensure_manager(&who, role_id)?;
+
+            if let (Some(from), Some(to)) = (active_from, active_to) {
+                if to < from {
+                    Err(Error::<T>::InvalidWindow)?
+                }
             }
 
-            Assignments::<T>::set(user.clone(), role_id, true);
+            role.active_from = active_from;
+            role.active_to = active_to;
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::insert(role_id, role);
 
-            Self::deposit_event(Event::RoleGranted { user, role_id });
+            Self::deposit_event(Event::RoleWindowSet {
+                role_id,
+                active_from,
+                active_to,
+            });
             Ok(())
         }
 
-        /// Revoke a role from the user
+        /// Grant a role to a batch of users
+        ///
+        /// Users who already hold the role are skipped.
+        /// The origin is authorized once for the whole batch: no `OnRoleChanged` handler runs during it,
+        /// so the origin's roles can't change before the batch ends.
         ///
         /// Parameters:
-        /// - `origin`: role revoker.
-        /// - `user`: account to revoke a role from.
-        /// - `role_id`: id of role to revoke.
+        /// - `origin`: role granter.
+        /// - `users`: role grantees.
+        /// - `role_id`: id of role to grant.
+        ///
+        /// Events:
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` for each user the role is granted to
+        /// - `BatchGrantCompleted(role_id, granted, skipped)` with the number of granted and skipped users
+        ///
+        /// Errors:
+        /// - `NotAuthorizedToGrant` if `origin` is not authorized to grant this role
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `TooManyFailedAttempts` if `origin` has failed to grant roles too many times
+        /// - `ConflictingRole` if any of `users` holds a role conflicting with this one
+        /// - `InvalidGrantee` if any of `users` is the zero account and `RejectDefaultAccount` is set
+        /// - `TooManyRolesForAccount` if any of `users` already holds `MaxRolesPerAccount` roles
+        /// - `Flapping` if the role was revoked from any of `users` less than `FlapCooldown` blocks ago
+        ///
+        /// Complexity:
+        ///  - O(G + N * C) where G is the number of the role granters, N is the number of users
+        ///    and C is the number of the role conflicts
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::grant_role_batch(
+            users.len() as u32,
+            T::GrantersListMaxLength::get(),
+            T::MaxConflicts::get(),
+        ))]
+        pub fn grant_role_batch(
+            origin: OriginFor<T>,
+            users: BoundedVec<T::AccountId, T::BatchMaxLength>,
+            role_id: T::RoleId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let batch = users.len() as u32;
+            Self::ensure_grant_not_locked(&who)?;
+            let role = Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorizedToGrant)?;
+
+            let mut granted = 0_u32;
+            let mut skipped = 0_u32;
+            for user in users {
+                if Assignments::<T>::contains_key(&user, role_id) {
+                    skipped += 1;
+                    continue;
+                }
+                Self::ensure_valid_grantee(&user)?;
+                Self::ensure_no_conflicts(&user, &role)?;
+                if !Self::can_hold_role(&user, role_id) {
+                    Err(Error::<T>::TooManyRolesForAccount)?
+                }
+                Self::ensure_not_flapping(&user, role_id)?;
+
+                Self::grant_as(who.clone(), &user, role_id);
+                granted += 1;
+
+                Self::deposit_event(Event::RoleGranted {
+                    user,
+                    role_id,
+                    newly_granted: true,
+                    total_holders: HolderCount::<T>::get(role_id),
+                });
+            }
+
+            Self::deposit_event(Event::BatchGrantCompleted {
+                role_id,
+                granted,
+                skipped,
+            });
+            Ok(Some(T::WeightInfo::grant_role_batch(
+                batch,
+                role.granters.len() as u32,
+                role.conflicts.len() as u32,
+            ))
+            .into())
+        }
+
+        /// Protect the role from losing its last holder
+        ///
+        /// Parameters:
+        /// - `origin`: `ProtectOrigin`.
+        /// - `role_id`: id of role to protect.
         ///
         /// Events:
-        /// - `RoleRevoked(user, role_id)` if role is revoked
+        /// - `RoleProtected(role_id)` if role is protected
         ///
         /// Errors:
-        /// - `NotAuthorized` if `origin` is not authorized to revoke this role
+        /// - `BadOrigin` if `origin` is not `ProtectOrigin`
         /// - `RoleNotExist`  if there is no role for this `role_id`
         ///
         /// Complexity:
         ///  - O(1)
-        #[pallet::call_index(1)]
-        #[pallet::weight(T::WeightInfo::revoke_role())]
-        pub fn revoke_role(
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::protect_role())]
+        pub fn protect_role(origin: OriginFor<T>, role_id: T::RoleId) -> DispatchResult {
+            T::ProtectOrigin::ensure_origin(origin)?;
+            if !Roles::<T>::contains_key(role_id) {
+                Err(Error::<T>::RoleNotExist)?
+            }
+
+            ProtectedRoles::<T>::insert(role_id, true);
+
+            Self::deposit_event(Event::RoleProtected { role_id });
+            Ok(())
+        }
+
+        /// Remove the protection from the role
+        ///
+        /// Parameters:
+        /// - `origin`: `ProtectOrigin`.
+        /// - `role_id`: id of role to unprotect.
+        ///
+        /// Events:
+        /// - `RoleUnprotected(role_id)` if role is unprotected
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ProtectOrigin`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::unprotect_role())]
+        pub fn unprotect_role(origin: OriginFor<T>, role_id: T::RoleId) -> DispatchResult {
+            T::ProtectOrigin::ensure_origin(origin)?;
+
+            ProtectedRoles::<T>::remove(role_id);
+
+            Self::deposit_event(Event::RoleUnprotected { role_id });
+            Ok(())
+        }
+
+        /// Explicitly deny a role to the user
+        ///
+        /// Denial beats assignment: authorization against a role list fails
+        /// if any of the roles is denied to the user, even if the user holds another one.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `user`: account to deny a role to.
+        /// - `role_id`: id of role to deny.
+        ///
+        /// Events:
+        /// - `RoleDenied(user, role_id)` if role is denied
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is not authorized to grant this role
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::deny_role())]
+        pub fn deny_role(
             origin: OriginFor<T>,
             user: T::AccountId,
             role_id: T::RoleId,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            let role = Roles::<T>::get(role_id);
+            Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorized)?;
 
-            let Some(role) = role else {
-                Err(Error::<T>::RoleNotExist)?
-            };
+            Denials::<T>::set(user.clone(), role_id, true);
+            RoleAccounts::<T>::insert(role_id, &user, ());
 
-            if !Pallet::<T>::authorize(&who, role.granters.as_slice()) {
-                Err(Error::<T>::NotAuthorized)?
-            }
+            Self::deposit_event(Event::RoleDenied { user, role_id });
+            Ok(())
+        }
+
+        /// Remove the denial of a role from the user
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `user`: account to remove the denial from.
+        /// - `role_id`: id of denied role.
+        ///
+        /// Events:
+        /// - `RoleUndenied(user, role_id)` if denial is removed
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is not authorized to grant this role
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::undeny_role())]
+        pub fn undeny_role(
+            origin: OriginFor<T>,
+            user: T::AccountId,
+            role_id: T::RoleId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorized)?;
 
-            Assignments::<T>::remove(user.clone(), role_id);
+            Denials::<T>::remove(user.clone(), role_id);
+            Self::unindex_role_account(&user, role_id);
 
-            Self::deposit_event(Event::RoleRevoked { user, role_id });
+            Self::deposit_event(Event::RoleUndenied { user, role_id });
             Ok(())
         }
-    }
 
-    impl<T: Config> Authorize<T::AccountId, T::RoleId> for Pallet<T> {
-        fn authorize(user: &T::AccountId, roles: &[T::RoleId]) -> bool {
-            for role in roles {
-                let authorized = Assignments::<T>::get(user, role);
-                if authorized {
-                    return true;
+        /// Remove the role with all of its assignments, denials, metadata and counters
+        ///
+        /// The role is removed with the first call, so it can't be granted anymore. The call is refused
+        /// if the role, its holders and metadata entries are more than `MaxPurge` items:
+        /// revoke some of the holders first, e.g. with `revoke_role_batch`.
+        /// At most `MaxPurge` items are removed per call, if there are more of them left, e.g. denials and
+        /// revocation records, the call should be repeated until `RolePurged` reports that the purge is complete.
+        /// Once it is complete, the id is reused by `add_role` if `RecycleRoleIds` is set.
+        /// `OnRoleChanged::on_role_removed` is called when the role is removed.
+        ///
+        /// Parameters:
+        /// - `origin`: `ForceOrigin`.
+        /// - `role_id`: id of role to purge.
+        ///
+        /// Events:
+        /// - `RolePurged(role_id, removed, complete)` with the number of removed items
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ForceOrigin`
+        /// - `RoleNotExist`  if there is no role for this `role_id` and nothing left to purge
+        /// - `TooManyToPurge` if the role with its holders and metadata entries are more than `MaxPurge` items
+        ///
+        /// Complexity:
+        ///  - O(P + C + D) where P is `MaxPurge`, C is the number of the role conflicts
+        ///    and D is the number of its dependencies and dependents
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::purge_role(
+            T::MaxPurge::get(),
+            T::MaxConflicts::get(),
+            T::MaxDependencies::get().saturating_mul(2),
+        ).saturating_add(T::OnRoleChanged::on_role_removed_weight()))]
+        pub fn purge_role(origin: OriginFor<T>, role_id: T::RoleId) -> DispatchResultWithPostInfo {
+            T::ForceOrigin::ensure_origin(origin)?;
+
+            let role = Roles::<T>::get(role_id);
+            let role_existed = role.is_some();
+            let mut removed = 0_u32;
+            let mut conflicts = 0_u32;
+            let mut links = 0_u32;
+            if let Some(role) = role {
+                let items = HolderCount::<T>::get(role_id)
+                    .saturating_add(RoleMetadataCount::<T>::get(role_id))
+                    .saturating_add(1);
+                if items > T::MaxPurge::get() {
+                    Err(Error::<T>::TooManyToPurge)?
                 }
+
+                Roles::<T>::remove(role_id);
+                let dependencies = RoleDependencies::<T>::take(role_id);
+                let dependents = RoleDependents::<T>::take(role_id);
+                conflicts = role.conflicts.len() as u32;
+                links = (dependencies.len() + dependents.len()) as u32;
+                Self::unlink_conflicts(role_id, &role.conflicts);
+                Self::unlink_dependencies(role_id, &dependencies);
+                for dependent in dependents {
+                    RoleDependencies::<T>::mutate(dependent, |dependencies| {
+                        dependencies.retain(|id| *id != role_id)
+                    });
+                }
+                RolePermissions::<T>::remove(role_id);
+                RoleUri::<T>::remove(role_id);
+                RoleNameIndex::<T>::remove(role.name, role_id);
+                ProtectedRoles::<T>::remove(role_id);
+                T::OnRoleChanged::on_role_removed(&role_id);
+                removed += 1;
             }
-            false
-        }
-    }
 
-    impl<T: Config> AddRole<T::RoleId> for Pallet<T> {
-        fn add_role(
-            name: &[u8],
-            granters: &[T::RoleId],
-            can_assign_itself: bool,
-        ) -> Result<T::RoleId, InterfaceError> {
-            let next_id = IdGenerator::<T>::mutate(|id| {
-                *id = id.increment();
-                *id
+            let limit = T::MaxPurge::get().saturating_sub(removed);
+            removed += RoleMetadata::<T>::clear_prefix(role_id, limit, None).unique;
+
+            let limit = T::MaxPurge::get().saturating_sub(removed) as usize;
+            let accounts: Vec<_> = RoleAccounts::<T>::iter_key_prefix(role_id)
+                .take(limit)
+                .collect();
+            let mut unassigned = 0_u32;
+            for user in accounts.iter() {
+                if Assignments::<T>::take(user, role_id).is_some() {
+                    AccountRoleCount::<T>::mutate(user, |count| *count = count.saturating_sub(1));
+                    Self::release_sponsored(user, role_id);
+                    unassigned += 1;
+                }
+                AssignmentGranter::<T>::remove(user, role_id);
+                AssignmentActiveFrom::<T>::remove(user, role_id);
+                AssignmentActiveTo::<T>::remove(user, role_id);
+                Denials::<T>::remove(user, role_id);
+                LastRevoked::<T>::remove(user, role_id);
+                RoleAccounts::<T>::remove(role_id, user);
+            }
+            if unassigned > 0 {
+                Self::note_membership_changed();
+            }
+            removed += accounts.len() as u32;
+
+            if !role_existed && removed == 0 {
+                Err(Error::<T>::RoleNotExist)?
+            }
+
+            let complete = RoleAccounts::<T>::iter_key_prefix(role_id).next().is_none()
+                && RoleMetadata::<T>::iter_key_prefix(role_id).next().is_none();
+            if complete {
+                HolderCount::<T>::remove(role_id);
+                RoleMetadataCount::<T>::remove(role_id);
+                if T::RecycleRoleIds::get() {
+                    // the id is lost for recycling if the stack is full
+                    let _ = FreedIds::<T>::try_append(role_id);
+                }
+            } else {
+                HolderCount::<T>::mutate(role_id, |count| {
+                    *count = count.saturating_sub(unassigned)
+                });
+            }
+
+            Self::deposit_event(Event::RolePurged {
+                role_id,
+                removed,
+                complete,
             });
-            let granters = if can_assign_itself {
-                [granters, &[next_id]].concat()
+            let callback = if role_existed {
+                T::OnRoleChanged::on_role_removed_weight()
             } else {
-                granters.to_vec()
+                Weight::zero()
             };
-            let role = RoleInfo {
-                name: name
-                    .to_vec()
-                    .try_into()
-                    .map_err(|_| InterfaceError::NameTooLong {
-                        expected: T::NameMaxLength::get(),
-                        observed: name.len(),
-                    })?,
-                granters: granters.clone().try_into().map_err(|_| {
-                    InterfaceError::GrantersListTooLong {
-                        expected: T::GrantersListMaxLength::get(),
-                        observed: granters.len(),
-                    }
-                })?,
+            Ok(
+                Some(T::WeightInfo::purge_role(removed, conflicts, links).saturating_add(callback))
+                    .into(),
+            )
+        }
+
+        /// Suspend all roles of the account without revoking them
+        ///
+        /// Suspended account fails every authorization until it is reinstated.
+        ///
+        /// Parameters:
+        /// - `origin`: `SuspendOrigin`.
+        /// - `user`: account to suspend.
+        ///
+        /// Events:
+        /// - `AccountSuspended(user)` if account is suspended
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `SuspendOrigin`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::suspend_account())]
+        pub fn suspend_account(origin: OriginFor<T>, user: T::AccountId) -> DispatchResult {
+            T::SuspendOrigin::ensure_origin(origin)?;
+
+            SuspendedAccounts::<T>::insert(user.clone(), true);
+
+            Self::deposit_event(Event::AccountSuspended { user });
+            Ok(())
+        }
+
+        /// Reinstate the roles of the suspended account
+        ///
+        /// Parameters:
+        /// - `origin`: `SuspendOrigin`.
+        /// - `user`: account to reinstate.
+        ///
+        /// Events:
+        /// - `AccountReinstated(user)` if account is reinstated
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `SuspendOrigin`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::reinstate_account())]
+        pub fn reinstate_account(origin: OriginFor<T>, user: T::AccountId) -> DispatchResult {
+            T::SuspendOrigin::ensure_origin(origin)?;
+
+            SuspendedAccounts::<T>::remove(user.clone());
+
+            Self::deposit_event(Event::AccountReinstated { user });
+            Ok(())
+        }
+
+        /// Set the roles conflicting with the role
+        ///
+        /// Conflicts are symmetric: each of `conflicts` gets `role_id` in its own conflicts
+        /// and the roles removed from the list lose it, so a single check on grant covers both directions.
+        /// Existing assignments are not affected.
+        ///
+        /// Parameters:
+        /// - `origin`: `ForceOrigin`.
+        /// - `role_id`: id of role to change.
+        /// - `conflicts`: ids of roles that can't be held together with this one.
+        ///
+        /// Events:
+        /// - `ConflictsSet(role_id, conflicts)` if conflicts are set
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ForceOrigin`
+        /// - `RoleNotExist`  if there is no role for `role_id` or for one of `conflicts`
+        /// - `InvalidConflict` if `conflicts` contain `role_id`
+        /// - `TooManyConflicts` if one of `conflicts` already has `MaxConflicts` conflicts
+        ///
+        /// Complexity:
+        ///  - O(C^2) where C is `MaxConflicts`
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::set_conflicts(T::MaxConflicts::get()))]
+        pub fn set_conflicts(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            conflicts: BoundedVec<T::RoleId, T::MaxConflicts>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            let Some(mut role) = Roles::<T>::get(role_id) else {
+                Err(Error::<T>::RoleNotExist)?
             };
-            Roles::<T>::set(next_id, Some(role.clone()));
-            Self::deposit_event(Event::RoleCreated {
-                id: next_id,
-                info: role,
+
+            let mut unique: BoundedVec<T::RoleId, T::MaxConflicts> = BoundedVec::default();
+            for conflict in conflicts {
+                if conflict == role_id {
+                    Err(Error::<T>::InvalidConflict)?
+                }
+                if !Roles::<T>::contains_key(conflict) {
+                    Err(Error::<T>::RoleNotExist)?
+                }
+                if !unique.contains(&conflict) {
+                    // can't overflow, `unique` is not longer than `conflicts`
+                    let _ = unique.try_push(conflict);
+                }
+            }
+
+            let removed: Vec<_> = role
+                .conflicts
+                .iter()
+                .filter(|conflict| !unique.contains(conflict))
+                .copied()
+                .collect();
+            Self::unlink_conflicts(role_id, &removed);
+            for conflict in unique
+                .iter()
+                .filter(|conflict| !role.conflicts.contains(conflict))
+            {
+                Roles::<T>::try_mutate(conflict, |other| -> DispatchResult {
+                    if let Some(other) = other {
+                        other
+                            .conflicts
+                            .try_push(role_id)
+                            .map_err(|_| Error::<T>::TooManyConflicts)?;
+                        other.modified_at = frame_system::Pallet::<T>::block_number();
+                    }
+                    Ok(())
+                })?;
+            }
+
+            role.conflicts = unique.clone();
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::insert(role_id, role);
+
+            Self::deposit_event(Event::ConflictsSet {
+                role_id,
+                conflicts: unique,
             });
-            Ok(next_id)
+            Ok(())
         }
-    }
 
-    impl<T: Config> PreassignRole<T::AccountId, T::RoleId> for Pallet<T> {
-        fn preassign_role(user: T::AccountId, role: T::RoleId) -> Result<(), InterfaceError> {
+        /// Revoke a role from a batch of users
+        ///
+        /// Users who don't hold the role are skipped.
+        /// Unlike `revoke_role`, the origin should be a granter of the role
+        /// even if it granted the role to all of the users.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `users`: accounts to revoke a role from.
+        /// - `role_id`: id of role to revoke.
+        ///
+        /// Events:
+        /// - `RoleRevoked(user, role_id, total_holders)` for each user the role is revoked from
+        /// - `BatchRevokeCompleted(role_id, revoked, skipped)` with the number of revoked and skipped users
+        ///
+        /// Errors:
+        /// - `NotAuthorizedToRevoke` if `origin` is not authorized to revoke this role
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `CannotRemoveLastHolderOfProtectedRole` if the role is protected and the batch revokes its last holder
+        ///
+        /// Complexity:
+        ///  - O(G + N) where G is the number of the role granters and N is the number of users
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::revoke_role_batch(
+            users.len() as u32,
+            T::GrantersListMaxLength::get(),
+        ))]
+        pub fn revoke_role_batch(
+            origin: OriginFor<T>,
+            users: BoundedVec<T::AccountId, T::BatchMaxLength>,
+            role_id: T::RoleId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let role = Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorizedToRevoke)?;
+            let batch = users.len() as u32;
+
+            let mut revoked = 0_u32;
+            let mut skipped = 0_u32;
+            for user in users {
+                if !Assignments::<T>::contains_key(&user, role_id) {
+                    skipped += 1;
+                    continue;
+                }
+
+                Self::revoke_as(&who, &user, role_id)?;
+                revoked += 1;
+
+                Self::deposit_event(Event::RoleRevoked {
+                    user,
+                    role_id,
+                    total_holders: HolderCount::<T>::get(role_id),
+                });
+            }
+
+            Self::deposit_event(Event::BatchRevokeCompleted {
+                role_id,
+                revoked,
+                skipped,
+            });
+            Ok(Some(T::WeightInfo::revoke_role_batch(
+                batch,
+                role.granters.len() as u32,
+            ))
+            .into())
+        }
+
+        /// Allow or forbid the role to grant itself
+        ///
+        /// The role id is added to or removed from the role granters.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `enabled`: whether the holders of the role can grant it.
+        ///
+        /// Events:
+        /// - `SelfAssignableChanged(role_id, enabled)` if the flag is changed
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `TooManyGranters` if the role already has `GrantersListMaxLength` granters
+        /// - `CannotRemoveLastGranter` if the role is its own only granter
+        ///
+        /// Complexity:
+        ///  - O(G) where G is the number of the role granters
+        #[pallet::call_index(13)]
+        #[pallet::weight(T::WeightInfo::set_self_assignable(T::GrantersListMaxLength::get()))]
+        pub fn set_self_assignable(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            enabled: bool,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let role = Self::ensure_manager(&who, role_id)?;
+            Self::set_self_granter(role_id, role, enabled)
+        }
+
+        /// Transfer the ownership of the role to another account
+        ///
+        /// Parameters:
+        /// - `origin`: role owner.
+        /// - `role_id`: id of role to transfer.
+        /// - `new_owner`: account that will own the role.
+        ///
+        /// Events:
+        /// - `RoleOwnershipTransferred(role_id, old_owner, new_owner)` if ownership is transferred
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is not the role owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(14)]
+        #[pallet::weight(T::WeightInfo::transfer_role_ownership())]
+        pub fn transfer_role_ownership(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            new_owner: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let Some(mut role) = Roles::<T>::get(role_id) else {
+                Err(Error::<T>::RoleNotExist)?
+            };
+            if role.owner.as_ref() != Some(&who) {
+                Err(Error::<T>::NotAuthorized)?
+            }
+
+            role.owner = Some(new_owner.clone());
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::insert(role_id, role);
+
+            Self::deposit_event(Event::RoleOwnershipTransferred {
+                role_id,
+                old_owner: who,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        /// Grant a role to the user that authorizes it only from `active_from` block
+        ///
+        /// The user holds the role right away, e.g. it counts towards `MaxRolesPerAccount`,
+        /// but it is not authorized by it before `active_from`. The role window still applies.
+        /// Granting the role again with `grant_role` activates it immediately.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
+        /// - `role_id`: id of role to grant.
+        /// - `active_from`: first block at which the assignment authorizes the user.
+        ///
+        /// Events:
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` if role is granted
+        /// - `RoleGrantScheduled(user, role_id, active_from)` with the activation block
+        ///
+        /// Errors:
+        /// - same as for `grant_role`
+        ///
+        /// Complexity:
+        ///  - O(G + C) where G is the number of the role granters and C is the number of its conflicts
+        #[pallet::call_index(15)]
+        #[pallet::weight(T::WeightInfo::grant_role_from(T::GrantersListMaxLength::get()))]
+        pub fn grant_role_from(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_id: T::RoleId,
+            active_from: BlockNumberFor<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let user = T::Lookup::lookup(user)?;
+
+            let granters = Self::do_grant(who, &user, role_id)?;
+            AssignmentActiveFrom::<T>::insert(&user, role_id, active_from);
+            AssignmentActiveTo::<T>::remove(&user, role_id);
+
+            Self::deposit_event(Event::RoleGrantScheduled {
+                user,
+                role_id,
+                active_from,
+            });
+            Ok(Some(T::WeightInfo::grant_role_from(granters)).into())
+        }
+
+        /// Add a permission to the role, the holders of the role are authorized for it
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `permission`: permission to add.
+        ///
+        /// Events:
+        /// - `PermissionAdded(role_id, permission)` if permission is added
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `TooManyPermissions` if the role already has `MaxPermissionsPerRole` permissions
+        ///
+        /// Complexity:
+        ///  - O(P) where P is the number of the role permissions
+        #[pallet::call_index(16)]
+        #[pallet::weight(T::WeightInfo::add_permission())]
+        pub fn add_permission(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            permission: T::PermissionId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_manager(&who, role_id)?;
+
+            RolePermissions::<T>::try_mutate(role_id, |permissions| {
+                if !permissions.contains(&permission) {
+                    permissions
+                        .try_push(permission)
+                        .map_err(|_| Error::<T>::TooManyPermissions)?;
+                }
+                Ok::<_, Error<T>>(())
+            })?;
+
+            Self::deposit_event(Event::PermissionAdded {
+                role_id,
+                permission,
+            });
+            Ok(())
+        }
+
+        /// Remove a permission from the role
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `permission`: permission to remove.
+        ///
+        /// Events:
+        /// - `PermissionRemoved(role_id, permission)` if permission is removed
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        ///
+        /// Complexity:
+        ///  - O(P) where P is the number of the role permissions
+        #[pallet::call_index(17)]
+        #[pallet::weight(T::WeightInfo::remove_permission())]
+        pub fn remove_permission(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            permission: T::PermissionId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_manager(&who, role_id)?;
+
+            RolePermissions::<T>::mutate(role_id, |permissions| {
+                permissions.retain(|other| *other != permission)
+            });
+
+            Self::deposit_event(Event::PermissionRemoved {
+                role_id,
+                permission,
+            });
+            Ok(())
+        }
+
+        /// Remove a granter from the role
+        ///
+        /// Removing the role's own id forbids the role to grant itself,
+        /// so it is done the same way as by `set_self_assignable`.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `granter`: id of granter role to remove.
+        ///
+        /// Events:
+        /// - `GranterRemoved(role_id, granter)` if the granter is removed
+        /// - `SelfAssignableChanged(role_id, enabled)` instead if `granter` is `role_id`
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `GranterNotFound` if `granter` is not a granter of the role
+        /// - `CannotRemoveLastGranter` if `granter` is the only granter of the role
+        ///
+        /// Complexity:
+        ///  - O(G) where G is the number of the role granters
+        #[pallet::call_index(18)]
+        #[pallet::weight(T::WeightInfo::remove_granter(T::GrantersListMaxLength::get()))]
+        pub fn remove_granter(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            granter: T::RoleId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let mut role = Self::ensure_manager(&who, role_id)?;
+            if !role.granters.contains(&granter) {
+                Err(Error::<T>::GranterNotFound)?
+            }
+            if granter == role_id {
+                return Self::set_self_granter(role_id, role, false);
+            }
+            if role.granters.len() == 1 {
+                Err(Error::<T>::CannotRemoveLastGranter)?
+            }
+
+            role.granters.retain(|id| *id != granter);
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::insert(role_id, role);
+
+            Self::deposit_event(Event::GranterRemoved { role_id, granter });
+            Ok(())
+        }
+
+        /// Set the metadata entry of the role, e.g. its description, tag or rank
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `key`: key of the entry, at most `MaxMetadataKeyLength` bytes.
+        /// - `value`: value of the entry, at most `MaxMetadataValueLength` bytes.
+        ///
+        /// Events:
+        /// - `RoleMetadataSet(role_id, key, value)` if the entry is set
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `TooManyMetadataEntries` if `key` is new and the role already has `MaxMetadataPerRole` entries
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(19)]
+        #[pallet::weight(T::WeightInfo::set_role_metadata())]
+        pub fn set_role_metadata(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            key: MetadataKeyOf<T>,
+            value: MetadataValueOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_manager(&who, role_id)?;
+
+            if !RoleMetadata::<T>::contains_key(role_id, &key) {
+                let count = RoleMetadataCount::<T>::get(role_id);
+                if count >= T::MaxMetadataPerRole::get() {
+                    Err(Error::<T>::TooManyMetadataEntries)?
+                }
+                RoleMetadataCount::<T>::insert(role_id, count + 1);
+            }
+            RoleMetadata::<T>::insert(role_id, &key, &value);
+
+            Self::deposit_event(Event::RoleMetadataSet {
+                role_id,
+                key,
+                value,
+            });
+            Ok(())
+        }
+
+        /// Grant a role to the user, creating the user's account if it doesn't exist
+        ///
+        /// The account gets a provider reference if it has none, so onboarded users without a balance
+        /// are not reaped. The reference is released when the role is revoked, reaped or purged,
+        /// so an account without a balance is reaped then.
+        /// The existential deposit is not transferred, as the pallet doesn't depend on a currency.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter sponsoring the user.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
+        /// - `role_id`: id of role to grant.
+        ///
+        /// Events:
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` if role is granted
+        /// - `AccountSponsored(user, sponsor)` if the account had no providers
+        ///
+        /// Errors:
+        /// - same as for `grant_role`
+        ///
+        /// Complexity:
+        ///  - O(G + C) where G is the number of the role granters and C is the number of its conflicts
+        #[pallet::call_index(20)]
+        #[pallet::weight(T::WeightInfo::sponsor_and_grant(T::GrantersListMaxLength::get()))]
+        pub fn sponsor_and_grant(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_id: T::RoleId,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let user = T::Lookup::lookup(user)?;
+
+            let granters = Self::do_grant(who.clone(), &user, role_id)?;
+            AssignmentActiveFrom::<T>::remove(&user, role_id);
+            AssignmentActiveTo::<T>::remove(&user, role_id);
+
+            if frame_system::Pallet::<T>::providers(&user) == 0 {
+                frame_system::Pallet::<T>::inc_providers(&user);
+                Sponsored::<T>::insert(&user, role_id, &who);
+                Self::deposit_event(Event::AccountSponsored { user, sponsor: who });
+            }
+
+            Ok(Some(T::WeightInfo::sponsor_and_grant(granters)).into())
+        }
+
+        /// Create the linked roles of the template and give its top role to the admin
+        ///
+        /// Parameters:
+        /// - `origin`: `ForceOrigin`.
+        /// - `template`: bundle of roles to create.
+        /// - `admin`: holder of the top role of the template, resolved by the runtime's `Lookup`.
+        ///
+        /// Events:
+        /// - `RoleCreated(id, info)` for each of the created roles
+        /// - `RolesCreatedFromTemplate(template, role_ids, admin)` with the ids of the created roles
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ForceOrigin`
+        /// - `LookupError` if `admin` can't be resolved
+        /// - `NameTooLong` if a role name of the template is longer than `NameMaxLength`
+        /// - `IdsExhausted` if there are no role ids left
+        /// - `InvalidGrantee` if `admin` is the zero account and `RejectDefaultAccount` is set
+        /// - `TooManyRolesForAccount` if `admin` already holds `MaxRolesPerAccount` roles
+        ///
+        /// Complexity:
+        ///  - O(R) where R is the number of the template roles
+        #[pallet::call_index(21)]
+        #[pallet::weight(T::WeightInfo::create_roles_from_template())]
+        pub fn create_roles_from_template(
+            origin: OriginFor<T>,
+            template: RoleTemplate,
+            admin: AccountIdLookupOf<T>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            let admin = T::Lookup::lookup(admin)?;
+
+            let role_ids = Self::add_roles_from_template(template).map_err(Error::<T>::from)?;
+            Self::preassign_role(admin.clone(), role_ids[0]).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::RolesCreatedFromTemplate {
+                template,
+                role_ids,
+                admin,
+            });
+            Ok(())
+        }
+
+        /// Set the roles the role depends on
+        ///
+        /// Holding the role requires holding each of `dependencies`: revoking one of them from the user
+        /// revokes the role too. Existing assignments are not affected.
+        ///
+        /// Parameters:
+        /// - `origin`: `ForceOrigin`.
+        /// - `role_id`: id of role to change.
+        /// - `dependencies`: ids of roles the role depends on.
+        ///
+        /// Events:
+        /// - `DependenciesSet(role_id, dependencies)` if dependencies are set
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ForceOrigin`
+        /// - `RoleNotExist`  if there is no role for `role_id` or for one of `dependencies`
+        /// - `InvalidDependency` if `dependencies` contain `role_id`
+        /// - `TooManyDependents` if one of `dependencies` already has `MaxDependencies` dependent roles
+        ///
+        /// Complexity:
+        ///  - O(D^2) where D is `MaxDependencies`
+        #[pallet::call_index(22)]
+        #[pallet::weight(T::WeightInfo::set_dependencies(T::MaxDependencies::get()))]
+        pub fn set_dependencies(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            dependencies: BoundedVec<T::RoleId, T::MaxDependencies>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            if !Roles::<T>::contains_key(role_id) {
+                Err(Error::<T>::RoleNotExist)?
+            }
+
+            let mut unique: BoundedVec<T::RoleId, T::MaxDependencies> = BoundedVec::default();
+            for dependency in dependencies {
+                if dependency == role_id {
+                    Err(Error::<T>::InvalidDependency)?
+                }
+                if !Roles::<T>::contains_key(dependency) {
+                    Err(Error::<T>::RoleNotExist)?
+                }
+                if !unique.contains(&dependency) {
+                    // can't overflow, `unique` is not longer than `dependencies`
+                    let _ = unique.try_push(dependency);
+                }
+            }
+
+            let current = RoleDependencies::<T>::get(role_id);
+            let removed: Vec<_> = current
+                .iter()
+                .filter(|dependency| !unique.contains(dependency))
+                .copied()
+                .collect();
+            Self::unlink_dependencies(role_id, &removed);
+            for dependency in unique
+                .iter()
+                .filter(|dependency| !current.contains(dependency))
+            {
+                RoleDependents::<T>::try_mutate(dependency, |dependents| {
+                    dependents
+                        .try_push(role_id)
+                        .map_err(|_| Error::<T>::TooManyDependents)
+                })?;
+            }
+            RoleDependencies::<T>::insert(role_id, &unique);
+
+            Self::deposit_event(Event::DependenciesSet {
+                role_id,
+                dependencies: unique,
+            });
+            Ok(())
+        }
+
+        /// Set the pointer to the off-chain metadata of the role, e.g. an IPFS CID of its description
+        ///
+        /// Parameters:
+        /// - `origin`: role granter or owner.
+        /// - `role_id`: id of role to change.
+        /// - `uri`: pointer to the metadata, at most `UriMaxLength` bytes, empty to remove it.
+        ///
+        /// Events:
+        /// - `RoleUriSet(role_id, uri)` if the pointer is set or removed
+        ///
+        /// Errors:
+        /// - `NotAuthorized` if `origin` is neither authorized to grant this role nor its owner
+        /// - `RoleNotExist`  if there is no role for this `role_id`
+        /// - `UriTooLong` if `uri` is longer than `UriMaxLength`
+        ///
+        /// Complexity:
+        ///  - O(G) where G is the number of the role granters, O(1) for the owner
+        #[pallet::call_index(23)]
+        #[pallet::weight(T::WeightInfo::set_role_uri(T::GrantersListMaxLength::get()))]
+        pub fn set_role_uri(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+            uri: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let role = Self::ensure_manager(&who, role_id)?;
+            let granters = if role.owner.as_ref() == Some(&who) {
+                0
+            } else {
+                role.granters.len() as u32
+            };
+            let uri: RoleUriOf<T> = uri.try_into().map_err(|_| Error::<T>::UriTooLong)?;
+
+            if uri.is_empty() {
+                RoleUri::<T>::remove(role_id);
+            } else {
+                RoleUri::<T>::insert(role_id, &uri);
+            }
+
+            Self::deposit_event(Event::RoleUriSet { role_id, uri });
+            Ok(Some(T::WeightInfo::set_role_uri(granters)).into())
+        }
+
+        /// Grant several roles to the user
+        ///
+        /// With `skip_conflicts` the roles conflicting with the held ones are skipped and reported
+        /// by `GrantPartial`, otherwise the first conflict fails the whole call.
+        /// A role conflicting with another role of the same call is skipped or fails the same way.
+        ///
+        /// Parameters:
+        /// - `origin`: granter of each of the roles.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
+        /// - `role_ids`: ids of roles to grant.
+        /// - `skip_conflicts`: skip the conflicting roles instead of failing.
+        ///
+        /// Events:
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` for each granted role
+        /// - `GrantPartial(user, granted, skipped_conflicts)` if any role was skipped
+        ///
+        /// Errors:
+        /// - same as for `grant_role`, `ConflictingRole` only if `skip_conflicts` is not set
+        ///
+        /// Complexity:
+        ///  - O(N * (G + C)) where N is the number of the roles, G is the number of the role granters
+        ///    and C is the number of its conflicts
+        #[pallet::call_index(24)]
+        #[pallet::weight(
+            T::WeightInfo::grant_roles(role_ids.len() as u32, T::GrantersListMaxLength::get())
+        )]
+        pub fn grant_roles(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_ids: BoundedVec<T::RoleId, T::BatchMaxLength>,
+            skip_conflicts: bool,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let user = T::Lookup::lookup(user)?;
+
+            let mut weight = Weight::zero();
+            let mut granted = Vec::new();
+            let mut skipped_conflicts = Vec::new();
+            for role_id in role_ids {
+                // the conflicts are checked before anything is written, so a skipped role leaves no changes
+                match Self::do_grant(who.clone(), &user, role_id) {
+                    Ok(granters) => {
+                        AssignmentActiveFrom::<T>::remove(&user, role_id);
+                        AssignmentActiveTo::<T>::remove(&user, role_id);
+                        weight.saturating_accrue(T::WeightInfo::grant_roles(1, granters));
+                        granted.push(role_id);
+                    }
+                    Err(error) if skip_conflicts && error == Error::<T>::ConflictingRole.into() => {
+                        weight.saturating_accrue(T::WeightInfo::grant_roles(
+                            1,
+                            T::GrantersListMaxLength::get(),
+                        ));
+                        skipped_conflicts.push(role_id);
+                    }
+                    Err(error) => Err(error)?,
+                }
+            }
+
+            if !skipped_conflicts.is_empty() {
+                Self::deposit_event(Event::GrantPartial {
+                    user,
+                    granted,
+                    skipped_conflicts,
+                });
+            }
+            Ok(Some(weight).into())
+        }
+
+        /// Grant a role to the user that authorizes it only from `active_from` to `active_to` block inclusive
+        ///
+        /// It is the per-assignment analog of `set_role_window`, e.g. to limit a contractor's access
+        /// to their engagement dates. The user holds the role for the whole time, e.g. it counts
+        /// towards `MaxRolesPerAccount`, until it is revoked. The role window still applies.
+        /// Granting the role again with `grant_role` removes the assignment window.
+        /// Once the window ends, the assignment is removed by `on_idle` when a block has weight to spare.
+        ///
+        /// Parameters:
+        /// - `origin`: role granter.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
+        /// - `role_id`: id of role to grant.
+        /// - `active_from`: first block at which the assignment authorizes the user.
+        /// - `active_to`: last block at which the assignment authorizes the user.
+        ///
+        /// Events:
+        /// - `RoleGranted(user, role_id, newly_granted, total_holders)` if role is granted
+        /// - `RoleGrantWindowed(user, role_id, active_from, active_to)` with the assignment window
+        ///
+        /// Errors:
+        /// - `InvalidWindow` if `active_to` is lower than `active_from`
+        /// - same as for `grant_role`
+        ///
+        /// Complexity:
+        ///  - O(G + C) where G is the number of the role granters and C is the number of its conflicts
+        #[pallet::call_index(25)]
+        #[pallet::weight(T::WeightInfo::grant_role_windowed(T::GrantersListMaxLength::get()))]
+        pub fn grant_role_windowed(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_id: T::RoleId,
+            active_from: BlockNumberFor<T>,
+            active_to: BlockNumberFor<T>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let user = T::Lookup::lookup(user)?;
+            if active_to < active_from {
+                Err(Error::<T>::InvalidWindow)?
+            }
+
+            let granters = Self::do_grant(who, &user, role_id)?;
+            AssignmentActiveFrom::<T>::insert(&user, role_id, active_from);
+            AssignmentActiveTo::<T>::insert(&user, role_id, active_to);
+
+            Self::deposit_event(Event::RoleGrantWindowed {
+                user,
+                role_id,
+                active_from,
+                active_to,
+            });
+            Ok(Some(T::WeightInfo::grant_role_windowed(granters)).into())
+        }
+
+        /// Preassign a role to the user without the grant authorization, e.g. to recover a role nobody holds
+        ///
+        /// Same as `PreassignRole::preassign_role` called from genesis or a runtime upgrade:
+        /// the granters, conflicts and grant limits of the role are not checked.
+        ///
+        /// Parameters:
+        /// - `origin`: `ForceOrigin`.
+        /// - `user`: role grantee, resolved by the runtime's `Lookup`.
+        /// - `role_id`: id of role to preassign.
+        ///
+        /// Events:
+        /// - `RolePreassigned(user, role_id)` if role is preassigned
+        ///
+        /// Errors:
+        /// - `BadOrigin` if `origin` is not `ForceOrigin`
+        /// - `LookupError` if `user` can't be resolved
+        /// - `RoleNotExist` if there is no role for this `role_id`
+        /// - `InvalidGrantee` if `user` is the zero account and `RejectDefaultAccount` is set
+        /// - `TooManyRolesForAccount` if `user` already holds `MaxRolesPerAccount` roles
+        ///
+        /// Complexity:
+        ///  - O(1)
+        #[pallet::call_index(26)]
+        #[pallet::weight(T::WeightInfo::force_preassign())]
+        pub fn force_preassign(
+            origin: OriginFor<T>,
+            user: AccountIdLookupOf<T>,
+            role_id: T::RoleId,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+            let user = T::Lookup::lookup(user)?;
+
+            Self::preassign_role(user.clone(), role_id).map_err(Error::<T>::from)?;
+
+            Self::deposit_event(Event::RolePreassigned { user, role_id });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Convert a signed origin into the `RoleHolder` origin if the signer holds the role
+        ///
+        /// **Errors**:
+        /// - `BadOrigin` if `origin` is not signed
+        /// - `NotAuthorized` if the signer doesn't hold the role
+        pub fn as_role_holder(
+            origin: OriginFor<T>,
+            role_id: T::RoleId,
+        ) -> Result<Origin<T>, DispatchError> {
+            let who = ensure_signed(origin)?;
+            if !Self::authorize(&who, &[role_id]) {
+                Err(Error::<T>::NotAuthorized)?
+            }
+            Ok(Origin::RoleHolder(who, role_id))
+        }
+
+        /// Get the roles from `candidates` that the user holds and that are active
+        ///
+        /// The order of `candidates` is preserved.
+        pub fn roles_held_among(user: &T::AccountId, candidates: &[T::RoleId]) -> Vec<T::RoleId> {
+            candidates
+                .iter()
+                .filter(|role| Self::holds_active_role(user, role))
+                .copied()
+                .collect()
+        }
+
+        /// Add a new role without depositing `RoleCreated`, e.g. when many roles are built at genesis
+        ///
+        /// Same as `AddRole::add_role` otherwise.
+        pub fn add_role_silent(
+            name: &[u8],
+            granters: &[T::RoleId],
+            can_assign_itself: bool,
+        ) -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let next_id = Self::next_role_id()?;
+            Self::create_role(next_id, name, granters, can_assign_itself, None, true)
+        }
+
+        /// Preassign a self-assignable role to the account, so it can grant the role at runtime
+        ///
+        /// Granters are roles, not accounts, so an account grants a role only by holding one of
+        /// its granters. A self-assignable role is its own granter, so its holder is its granter too.
+        /// Roles that are not self-assignable are rejected with `NotSelfAssignable`,
+        /// preassign one of their granters instead.
+        /// This should be called only from `GenesisBuild` or `Hooks::on_runtime_upgrade`.
+        pub fn preassign_granter(
+            account: T::AccountId,
+            role_id: T::RoleId,
+        ) -> Result<(), InterfaceError<T::RoleId>> {
+            let Some(role) = Roles::<T>::get(role_id) else {
+                Err(InterfaceError::RoleNotExist { role: role_id })?
+            };
+            if !role.granters.contains(&role_id) {
+                Err(InterfaceError::NotSelfAssignable { role: role_id })?
+            }
+            Self::preassign_role(account, role_id)
+        }
+
+        /// Create the linked roles of the template, returning their ids in the order of the template
+        pub fn add_roles_from_template(
+            template: RoleTemplate,
+        ) -> Result<Vec<T::RoleId>, InterfaceError<T::RoleId>> {
+            match template {
+                RoleTemplate::AdminEditorViewer => {
+                    let admin = Self::add_role("admin".as_bytes(), &[], true)?;
+                    let editor = Self::add_role("editor".as_bytes(), &[admin], false)?;
+                    let viewer = Self::add_role("viewer".as_bytes(), &[editor], false)?;
+                    Ok(Vec::from([admin, editor, viewer]))
+                }
+            }
+        }
+
+        /// Get all assignments of the active roles sorted by account and role id
+        ///
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain,
+        /// e.g. from `RbacApi::all_assignments`. Sorting adds O(N log N) of the result size.
+        pub fn all_assignments() -> Vec<(T::AccountId, T::RoleId)> {
+            let mut assignments: Vec<_> = Assignments::<T>::iter()
+                .filter(|(_, role_id, _)| Self::is_role_active(role_id))
+                .map(|(user, role_id, _)| (user, role_id))
+                .collect();
+            assignments.sort();
+            assignments
+        }
+
+        /// Get all metadata entries of the role
+        pub fn role_metadata_entries(role_id: T::RoleId) -> Vec<(Vec<u8>, Vec<u8>)> {
+            RoleMetadata::<T>::iter_prefix(role_id)
+                .map(|(key, value)| (key.into_inner(), value.into_inner()))
+                .collect()
+        }
+
+        /// Check which of the roles exist, e.g. to validate the required roles of a consumer
+        ///
+        /// The result is in the order of `role_ids`.
+        pub fn roles_exist(role_ids: &[T::RoleId]) -> Vec<bool> {
+            role_ids
+                .iter()
+                .map(|role_id| Roles::<T>::contains_key(role_id))
+                .collect()
+        }
+
+        /// Check which of the roles the granter can grant, e.g. to show an admin the roles they manage
+        ///
+        /// The result is in the order of `role_ids`, missing roles can't be granted.
+        /// It checks the granter the same way as `grant_role` does.
+        pub fn grantable_by(granter: &T::AccountId, role_ids: &[T::RoleId]) -> Vec<bool> {
+            role_ids
+                .iter()
+                .map(|role_id| {
+                    Self::ensure_granter(granter, *role_id, Error::<T>::NotAuthorizedToGrant)
+                        .is_ok()
+                })
+                .collect()
+        }
+
+        /// Authorize each of the users against the role, e.g. to render a table of accounts
+        ///
+        /// Same as calling `Authorize::authorize` with the single role for every user,
+        /// the result is in the order of `users`. Repeated users are checked once per call,
+        /// the results are memoized only for the duration of the call and never persisted.
+        pub fn authorize_many(users: &[T::AccountId], role_id: &T::RoleId) -> Vec<bool> {
+            let roles = core::slice::from_ref(role_id);
+            let mut checked = BTreeMap::new();
+            users
+                .iter()
+                .map(|user| {
+                    *checked
+                        .entry(user)
+                        .or_insert_with(|| Self::authorize(user, roles))
+                })
+                .collect()
+        }
+
+        /// Build the proof of the assignment against `MembershipRoot`, `None` if the role is not assigned to the user
+        ///
+        /// The proof matches the root stored at the end of the block, so query it at a finalized state.
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain,
+        /// e.g. from `RbacApi::membership_proof`.
+        pub fn membership_proof(
+            user: &T::AccountId,
+            role_id: T::RoleId,
+        ) -> Option<merkle::MembershipProof<T::Hash>> {
+            let assignments = Self::sorted_assignments();
+            if assignments.len() > T::MaxMembershipSize::get() as usize {
+                return None;
+            }
+            let index = assignments.binary_search(&(user.clone(), role_id)).ok()?;
+            merkle::proof::<T::Hashing>(&Self::membership_leaves(&assignments), index)
+        }
+
+        /// Get the content hash of the role, e.g. for clients to skip re-fetching unchanged roles
+        ///
+        /// The role is hashed by the runtime's `Hashing`, see `RoleInfo::content_hash`.
+        pub fn role_hash(role_id: T::RoleId) -> Option<T::Hash> {
+            Roles::<T>::get(role_id).map(|role| role.content_hash::<T::Hashing>())
+        }
+
+        /// Get the active roles of the user sorted by id
+        ///
+        /// A role is listed if both the role and the user's assignment are within their windows.
+        /// Sorting adds O(N log N) of the result size, as the storage order depends on the key hashes.
+        pub fn roles_of(user: &T::AccountId) -> Vec<T::RoleId> {
+            let mut roles: Vec<_> = Assignments::<T>::iter_key_prefix(user)
+                .filter(|role_id| Self::holds_active_role(user, role_id))
+                .collect();
+            roles.sort();
+            roles
+        }
+
+        /// Get at most `limit` active roles of the user following `start_after`, e.g. to list the roles of power users
+        ///
+        /// The roles are in the storage order rather than sorted by id. `start_after` is an opaque cursor:
+        /// pass the last role of the page to get the next one, `None` starts from the beginning.
+        /// An empty page means there are no more roles.
+        pub fn roles_of_paged(
+            user: &T::AccountId,
+            start_after: Option<T::RoleId>,
+            limit: u32,
+        ) -> Vec<T::RoleId> {
+            let roles = match start_after {
+                Some(role_id) => Assignments::<T>::iter_key_prefix_from(
+                    user,
+                    Assignments::<T>::hashed_key_for(user, role_id),
+                ),
+                None => Assignments::<T>::iter_key_prefix(user),
+            };
+            roles
+                .filter(|role_id| Self::holds_active_role(user, role_id))
+                .take(limit as usize)
+                .collect()
+        }
+
+        /// Get the block at which the role was granted to the user, `None` if the user doesn't hold it
+        ///
+        /// It is a single read of `Assignments`, which keeps the block of every assignment,
+        /// so "when did the user get the role?" needs no separate index. Revocation removes the record.
+        pub fn granted_at(user: &T::AccountId, role_id: T::RoleId) -> Option<BlockNumberFor<T>> {
+            Assignments::<T>::get(user, role_id).map(|info| info.granted_at)
+        }
+
+        /// Get all roles assigned to the user sorted by id, with the last block of their active window
+        ///
+        /// Unlike `roles_of` it includes the roles whose window has ended, e.g. for admin UIs showing the history.
+        /// The block is the earlier of the ends of the role window and the assignment window,
+        /// `None` if both of them are open-ended.
+        pub fn roles_of_including_expired(
+            user: &T::AccountId,
+        ) -> Vec<(T::RoleId, Option<BlockNumberFor<T>>)> {
+            let mut roles: Vec<_> = Assignments::<T>::iter_key_prefix(user)
+                .filter_map(|role_id| {
+                    let role = Roles::<T>::get(role_id)?;
+                    let active_to =
+                        match (role.active_to, AssignmentActiveTo::<T>::get(user, role_id)) {
+                            (Some(role_to), Some(assignment_to)) => {
+                                Some(role_to.min(assignment_to))
+                            }
+                            (role_to, assignment_to) => role_to.or(assignment_to),
+                        };
+                    Some((role_id, active_to))
+                })
+                .collect();
+            roles.sort();
+            roles
+        }
+
+        /// Get the holders of the role sorted by account, empty if the role is not active
+        ///
+        /// The holders whose assignment is outside of its window are skipped.
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain,
+        /// e.g. from `RbacApi::role_holders`. Sorting adds O(N log N) of the result size.
+        pub fn role_holders(role_id: T::RoleId) -> Vec<T::AccountId> {
+            if !Self::is_role_active(&role_id) {
+                return Vec::new();
+            }
+            let mut holders: Vec<_> = Assignments::<T>::iter_keys()
+                .filter(|(user, role)| *role == role_id && Self::holds_active_role(user, role))
+                .map(|(user, _)| user)
+                .collect();
+            holders.sort();
+            holders
+        }
+
+        /// Get the roles to grant to and to revoke from the user so it holds exactly the `desired` roles
+        ///
+        /// Roles are compared with the assignments only, their windows, denials and suspensions are ignored.
+        /// Duplicates in `desired` are listed once.
+        ///
+        /// **Returns**: `(to_grant, to_revoke)`
+        pub fn reconcile_plan(
+            user: &T::AccountId,
+            desired: &[T::RoleId],
+        ) -> (Vec<T::RoleId>, Vec<T::RoleId>) {
+            let current: Vec<_> = Assignments::<T>::iter_key_prefix(user).collect();
+
+            let mut to_grant: Vec<T::RoleId> = Vec::new();
+            for role_id in desired {
+                if !current.contains(role_id) && !to_grant.contains(role_id) {
+                    to_grant.push(*role_id);
+                }
+            }
+            let to_revoke = current
+                .into_iter()
+                .filter(|role_id| !desired.contains(role_id))
+                .collect();
+            (to_grant, to_revoke)
+        }
+
+        /// Get a role held by the user that conflicts with the role, if any
+        pub fn conflicting_role(user: &T::AccountId, role_id: T::RoleId) -> Option<T::RoleId> {
+            Roles::<T>::get(role_id)?
+                .conflicts
+                .into_iter()
+                .find(|conflict| Assignments::<T>::contains_key(user, conflict))
+        }
+
+        /// Check that the user doesn't hold any role conflicting with the role
+        fn ensure_no_conflicts(user: &T::AccountId, role: &RoleInfoOf<T>) -> DispatchResult {
+            if role
+                .conflicts
+                .iter()
+                .any(|conflict| Assignments::<T>::contains_key(user, conflict))
+            {
+                Err(Error::<T>::ConflictingRole)?
+            }
+            Ok(())
+        }
+
+        /// Check that the user is not the zero account if `RejectDefaultAccount` is set.
+        /// `AccountId` has no `Default` bound, so the account decoded from zeroes is used instead.
+        fn ensure_valid_grantee(user: &T::AccountId) -> DispatchResult {
+            if T::RejectDefaultAccount::get()
+                && T::AccountId::decode(&mut TrailingZeroInput::zeroes()).as_ref() == Ok(user)
+            {
+                Err(Error::<T>::InvalidGrantee)?
+            }
+            Ok(())
+        }
+
+        /// Remove the role from the conflicts of each of `conflicts`
+        fn unlink_conflicts(role_id: T::RoleId, conflicts: &[T::RoleId]) {
+            for conflict in conflicts {
+                Roles::<T>::mutate(conflict, |other| {
+                    if let Some(other) = other {
+                        other.conflicts.retain(|id| *id != role_id);
+                        other.modified_at = frame_system::Pallet::<T>::block_number();
+                    }
+                });
+            }
+        }
+
+        /// Remove the role from the dependents of each of the dependencies
+        fn unlink_dependencies(role_id: T::RoleId, dependencies: &[T::RoleId]) {
+            for dependency in dependencies {
+                RoleDependents::<T>::mutate(dependency, |dependents| {
+                    dependents.retain(|id| *id != role_id)
+                });
+            }
+        }
+
+        /// Assign the role to the user, returns `true` if the user didn't hold it before
+        fn assign(user: &T::AccountId, role_id: T::RoleId, granter: Option<T::AccountId>) -> bool {
+            let is_new = !Assignments::<T>::contains_key(user, role_id);
+            if is_new {
+                let info = AssignmentInfo {
+                    granted_at: frame_system::Pallet::<T>::block_number(),
+                    extrinsic_index: frame_system::Pallet::<T>::extrinsic_index(),
+                };
+                Assignments::<T>::insert(user, role_id, info);
+                RoleAccounts::<T>::insert(role_id, user, ());
+                Self::note_membership_changed();
+                HolderCount::<T>::mutate(role_id, |count| *count = count.saturating_add(1));
+                AccountRoleCount::<T>::mutate(user, |count| *count = count.saturating_add(1));
+                LastRevoked::<T>::remove(user, role_id);
+            }
+            if let Some(granter) = granter {
+                AssignmentGranter::<T>::insert(user, role_id, granter);
+            }
+            is_new
+        }
+
+        /// Release the provider reference given to the user by `sponsor_and_grant` for the role
+        fn release_sponsored(user: &T::AccountId, role_id: T::RoleId) {
+            if Sponsored::<T>::take(user, role_id).is_some() {
+                // the reference stays if the account still has consumers, as it can't be reaped anyway
+                let _ = frame_system::Pallet::<T>::dec_providers(user);
+            }
+        }
+
+        /// Remove the user from `RoleAccounts` of the role if nothing of the role is left for the user
+        fn unindex_role_account(user: &T::AccountId, role_id: T::RoleId) {
+            if !Assignments::<T>::contains_key(user, role_id)
+                && !Denials::<T>::contains_key(user, role_id)
+                && !LastRevoked::<T>::contains_key(user, role_id)
+            {
+                RoleAccounts::<T>::remove(role_id, user);
+            }
+        }
+
+        /// Check that the user already holds the role or holds less than `MaxRolesPerAccount` roles
+        fn can_hold_role(user: &T::AccountId, role_id: T::RoleId) -> bool {
+            Assignments::<T>::contains_key(user, role_id)
+                || AccountRoleCount::<T>::get(user) < T::MaxRolesPerAccount::get()
+        }
+
+        /// Grant the role to the user on behalf of `who` checking all the restrictions
+        ///
+        /// **Returns**: number of the role granters
+        fn do_grant(
+            who: T::AccountId,
+            user: &T::AccountId,
+            role_id: T::RoleId,
+        ) -> Result<u32, DispatchError> {
+            Self::ensure_grant_not_locked(&who)?;
+            let role = Self::ensure_granter(&who, role_id, Error::<T>::NotAuthorizedToGrant)?;
+            Self::ensure_valid_grantee(user)?;
+            Self::ensure_no_conflicts(user, &role)?;
+            if !Self::can_hold_role(user, role_id) {
+                Err(Error::<T>::TooManyRolesForAccount)?
+            }
+            Self::ensure_not_flapping(user, role_id)?;
+
+            let newly_granted = Self::grant_as(who, user, role_id);
+
+            Self::deposit_event(Event::RoleGranted {
+                user: user.clone(),
+                role_id,
+                newly_granted,
+                total_holders: HolderCount::<T>::get(role_id),
+            });
+            Ok(role.granters.len() as u32)
+        }
+
+        /// Assign the role to the user on behalf of `who`, recording the grant in the audit log
+        ///
+        /// **Returns**: `true` if the user didn't hold the role before
+        fn grant_as(who: T::AccountId, user: &T::AccountId, role_id: T::RoleId) -> bool {
+            Self::record_audit(&who, user, role_id, AuditAction::Grant);
+            frame_support::log::debug!(
+                target: LOG_TARGET,
+                "role {:?} granted to {:?} by {:?}",
+                role_id,
+                user,
+                who,
+            );
+            Self::assign(user, role_id, Some(who))
+        }
+
+        /// Remove the role from the user on behalf of `who`, recording the revocation in the audit log
+        fn revoke_as(
+            who: &T::AccountId,
+            user: &T::AccountId,
+            role_id: T::RoleId,
+        ) -> DispatchResult {
+            Self::unassign(user, role_id)?;
+            Self::record_audit(who, user, role_id, AuditAction::Revoke);
+            frame_support::log::debug!(
+                target: LOG_TARGET,
+                "role {:?} revoked from {:?} by {:?}",
+                role_id,
+                user,
+                who,
+            );
+            Ok(())
+        }
+
+        /// Remove the role from the user, keeping at least one holder of a protected role
+        fn unassign(user: &T::AccountId, role_id: T::RoleId) -> DispatchResult {
+            if Assignments::<T>::contains_key(user, role_id) {
+                let count = HolderCount::<T>::get(role_id);
+                if count <= 1 && ProtectedRoles::<T>::get(role_id) {
+                    Err(Error::<T>::CannotRemoveLastHolderOfProtectedRole)?
+                }
+                Assignments::<T>::remove(user, role_id);
+                Self::note_membership_changed();
+                HolderCount::<T>::insert(role_id, count.saturating_sub(1));
+                AccountRoleCount::<T>::mutate(user, |count| *count = count.saturating_sub(1));
+                if !T::FlapCooldown::get().is_zero() {
+                    LastRevoked::<T>::insert(
+                        user,
+                        role_id,
+                        frame_system::Pallet::<T>::block_number(),
+                    );
+                }
+                Self::unindex_role_account(user, role_id);
+                Self::release_sponsored(user, role_id);
+            }
+            AssignmentGranter::<T>::remove(user, role_id);
+            AssignmentActiveFrom::<T>::remove(user, role_id);
+            AssignmentActiveTo::<T>::remove(user, role_id);
+            Ok(())
+        }
+
+        /// Remove as many assignments whose window has ended as fit into `remaining_weight`
+        ///
+        /// It is best-effort: the windows are visited from `ReapCursor` in the storage order
+        /// and the cursor is moved past the visited ones, so the next call resumes there.
+        /// The last holder of a protected role keeps it. Returns the consumed weight.
+        fn reap_expired_assignments(remaining_weight: Weight) -> Weight {
+            if !T::WeightInfo::reap_expired_assignments(1).all_lte(remaining_weight) {
+                return Weight::zero();
+            }
+
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut windows = match ReapCursor::<T>::get() {
+                Some((user, role_id)) => AssignmentActiveTo::<T>::iter_from(
+                    AssignmentActiveTo::<T>::hashed_key_for(user, role_id),
+                ),
+                None => AssignmentActiveTo::<T>::iter(),
+            };
+            let mut visited = 0_u32;
+            let mut cursor = None;
+            let mut expired = Vec::new();
+            while T::WeightInfo::reap_expired_assignments(visited.saturating_add(1))
+                .all_lte(remaining_weight)
+            {
+                let Some((user, role_id, active_to)) = windows.next() else {
+                    // the end of the storage is reached, the next cleanup starts over
+                    cursor = None;
+                    break;
+                };
+                visited += 1;
+                if active_to < now {
+                    expired.push((user.clone(), role_id));
+                }
+                cursor = Some((user, role_id));
+            }
+            ReapCursor::<T>::set(cursor);
+
+            for (user, role_id) in expired {
+                if Self::unassign(&user, role_id).is_ok() {
+                    Self::deposit_event(Event::AssignmentExpired { user, role_id });
+                }
+            }
+            T::WeightInfo::reap_expired_assignments(visited)
+        }
+
+        /// Generate a new role id and save it as the latest one
+        /// Ids of the reserved ranges and the ids taken by other roles are skipped.
+        fn next_role_id() -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let reserved: Vec<_> = ReservedIds::<T>::iter().collect();
+            let reserved_end = |id: &T::RoleId| {
+                reserved
+                    .iter()
+                    .find(|(start, (end, _))| start <= id && id <= end)
+                    .map(|(_, end)| *end)
+            };
+
+            if T::RecycleRoleIds::get() {
+                let recycled = FreedIds::<T>::mutate(|ids| {
+                    while let Some(id) = ids.pop() {
+                        if !Roles::<T>::contains_key(id) && reserved_end(&id).is_none() {
+                            return Some(id);
+                        }
+                    }
+                    None
+                });
+                if let Some(id) = recycled {
+                    return Ok(id);
+                }
+            }
+            loop {
+                let count = IdCounter::<T>::get()
+                    .checked_add(1)
+                    .ok_or(InterfaceError::IdsExhausted)?;
+                let id = T::IdAllocator::allocate(&IdGenerator::<T>::get(), count)
+                    .ok_or(InterfaceError::IdsExhausted)?;
+                if let Some((end, end_count)) = reserved_end(&id) {
+                    // jump over the range, the next id is allocated after its last one
+                    IdGenerator::<T>::set(end);
+                    IdCounter::<T>::set(end_count.max(count));
+                    continue;
+                }
+                IdGenerator::<T>::set(id);
+                IdCounter::<T>::set(count);
+                if !Roles::<T>::contains_key(id) {
+                    return Ok(id);
+                }
+            }
+        }
+
+        /// Create the role with the given id, the id should be free.
+        /// `RoleCreated` is not deposited if `silent` is set.
+        pub(crate) fn create_role(
+            id: T::RoleId,
+            name: &[u8],
+            granters: &[T::RoleId],
+            can_assign_itself: bool,
+            owner: Option<T::AccountId>,
+            silent: bool,
+        ) -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let mut role =
+                RoleInfoOf::<T>::try_new(id, name, granters, can_assign_itself, |granter| {
+                    Roles::<T>::contains_key(granter)
+                })?;
+            role.owner = owner;
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::set(id, Some(role.clone()));
+            RoleNameIndex::<T>::insert(role.name.clone(), id, ());
+            if !silent {
+                Self::deposit_event(Event::RoleCreated { id, info: role });
+            }
+            Ok(id)
+        }
+
+        /// Check that every role can be granted by somebody.
+        ///
+        /// Roles with a holder and self-assignable roles are grantable, then every role
+        /// with a grantable granter is grantable too, until nothing changes. So a chain like
+        /// viewer <- editor <- admin needs only a holder of admin.
+        ///
+        /// Panics with the id and the name of the first role that can't be granted.
+        fn ensure_roles_grantable() {
+            let roles: Vec<_> = Roles::<T>::iter().collect();
+            let mut grantable: Vec<T::RoleId> = roles
+                .iter()
+                .filter(|(role_id, role)| {
+                    HolderCount::<T>::get(role_id) > 0 || role.granters.contains(role_id)
+                })
+                .map(|(role_id, _)| *role_id)
+                .collect();
+            loop {
+                let found = grantable.len();
+                for (role_id, role) in roles.iter() {
+                    if !grantable.contains(role_id)
+                        && role
+                            .granters
+                            .iter()
+                            .any(|granter| grantable.contains(granter))
+                    {
+                        grantable.push(*role_id);
+                    }
+                }
+                if grantable.len() == found {
+                    break;
+                }
+            }
+            for (role_id, role) in roles.iter() {
+                assert!(
+                    grantable.contains(role_id),
+                    "role {:?} ({}) has no holders, is not self-assignable and none of its granters \
+                     can be granted, nobody will be able to grant it",
+                    role_id,
+                    core::str::from_utf8(&role.name).unwrap_or("<non utf-8 name>"),
+                );
+            }
+        }
+
+        /// Number of failed grant attempts of the account in the current window
+        fn current_failed_attempts(who: &T::AccountId) -> u32 {
+            let Some((window_start, attempts)) = FailedGrantAttempts::<T>::get(who) else {
+                return 0;
+            };
+            let now = frame_system::Pallet::<T>::block_number();
+            if now >= window_start.saturating_add(T::ResetWindow::get()) {
+                0
+            } else {
+                attempts
+            }
+        }
+
+        /// Check that the account hasn't exceeded the number of failed grant attempts
+        fn ensure_grant_not_locked(who: &T::AccountId) -> DispatchResult {
+            if Self::current_failed_attempts(who) >= T::MaxFailedAttempts::get() {
+                Err(Error::<T>::TooManyFailedAttempts)?
+            }
+            Ok(())
+        }
+
+        /// Add or remove the role's own id from its granters and save the role
+        fn set_self_granter(
+            role_id: T::RoleId,
+            mut role: RoleInfoOf<T>,
+            enabled: bool,
+        ) -> DispatchResult {
+            let is_granter = role.granters.contains(&role_id);
+            if enabled && !is_granter {
+                role.granters
+                    .try_push(role_id)
+                    .map_err(|_| Error::<T>::TooManyGranters)?;
+            } else if !enabled && is_granter {
+                if role.granters.len() == 1 {
+                    Err(Error::<T>::CannotRemoveLastGranter)?
+                }
+                role.granters.retain(|granter| *granter != role_id);
+            }
+            role.modified_at = frame_system::Pallet::<T>::block_number();
+            Roles::<T>::insert(role_id, role);
+
+            Self::deposit_event(Event::SelfAssignableChanged { role_id, enabled });
+            Ok(())
+        }
+
+        /// Check that the role wasn't revoked from the user less than `FlapCooldown` blocks ago
+        fn ensure_not_flapping(user: &T::AccountId, role_id: T::RoleId) -> DispatchResult {
+            if let Some(revoked_at) = LastRevoked::<T>::get(user, role_id) {
+                let now = frame_system::Pallet::<T>::block_number();
+                if now < revoked_at.saturating_add(T::FlapCooldown::get()) {
+                    Err(Error::<T>::Flapping)?
+                }
+            }
+            Ok(())
+        }
+
+        /// Count a failed grant attempt, starting a new window if the previous one has passed
+        pub(crate) fn note_failed_grant(who: &T::AccountId) {
+            let attempts = Self::current_failed_attempts(who);
+            let window_start = match FailedGrantAttempts::<T>::get(who) {
+                Some((window_start, _)) if attempts > 0 => window_start,
+                _ => frame_system::Pallet::<T>::block_number(),
+            };
+            FailedGrantAttempts::<T>::insert(who, (window_start, attempts.saturating_add(1)));
+        }
+
+        /// Log the id of the missing role and return `RoleNotExist`.
+        /// The error can't carry the id, as it may not fit into the module error, and the events
+        /// of a failed call are discarded together with its storage changes, so the log is the only trace.
+        fn role_not_exist(role_id: T::RoleId) -> Error<T> {
+            frame_support::log::debug!(target: LOG_TARGET, "role {:?} doesn't exist", role_id);
+            Error::<T>::RoleNotExist
+        }
+
+        /// Load the role and check that `who` is one of its granters, failing with `denied` if it isn't
+        fn ensure_granter(
+            who: &T::AccountId,
+            role_id: T::RoleId,
+            denied: Error<T>,
+        ) -> Result<RoleInfoOf<T>, DispatchError> {
+            let Some(role) = Roles::<T>::get(role_id) else {
+                Err(Self::role_not_exist(role_id))?
+            };
+
+            if role.granters.is_empty() || !Pallet::<T>::authorize(who, role.granters.as_slice()) {
+                Err(denied)?
+            }
+
+            Ok(role)
+        }
+
+        /// Load the role and check that `who` is its owner or one of its granters
+        fn ensure_manager(
+            who: &T::AccountId,
+            role_id: T::RoleId,
+        ) -> Result<RoleInfoOf<T>, DispatchError> {
+            match Roles::<T>::get(role_id) {
+                Some(role) if role.owner.as_ref() == Some(who) => Ok(role),
+                _ => Self::ensure_granter(who, role_id, Error::<T>::NotAuthorized),
+            }
+        }
+
+        /// Mark `MembershipRoot` to be recomputed at the end of the block
+        fn note_membership_changed() {
+            if T::TrackMembershipRoot::get() {
+                MembershipDirty::<T>::put(true);
+            }
+        }
+
+        /// All assignments sorted by account and role, including the inactive ones
+        fn sorted_assignments() -> Vec<(T::AccountId, T::RoleId)> {
+            let mut assignments: Vec<_> = Assignments::<T>::iter_keys().collect();
+            assignments.sort();
+            assignments
+        }
+
+        fn membership_leaves(assignments: &[(T::AccountId, T::RoleId)]) -> Vec<T::Hash> {
+            assignments
+                .iter()
+                .map(|(user, role_id)| merkle::leaf_hash::<T::Hashing, _, _>(user, role_id))
+                .collect()
+        }
+
+        /// Recompute `MembershipRoot` over all assignments, reset it if there are more than `MaxMembershipSize`
+        fn update_membership_root() {
+            let max = T::MaxMembershipSize::get() as usize;
+            let mut assignments: Vec<_> = Assignments::<T>::iter_keys()
+                .take(max.saturating_add(1))
+                .collect();
+            if assignments.len() > max {
+                frame_support::log::warn!(
+                    target: LOG_TARGET,
+                    "more than {} assignments, membership root is not tracked",
+                    max
+                );
+                MembershipRoot::<T>::kill();
+                MembershipSize::<T>::kill();
+                return;
+            }
+
+            assignments.sort();
+            let leaves = Self::membership_leaves(&assignments);
+            MembershipRoot::<T>::put(merkle::root::<T::Hashing>(&leaves));
+            MembershipSize::<T>::put(leaves.len() as u32);
+        }
+
+        /// Check that the user is assigned to the role, the assignment is within its window and the role is active
+        fn holds_active_role(user: &T::AccountId, role_id: &T::RoleId) -> bool {
+            let now = frame_system::Pallet::<T>::block_number();
+            Assignments::<T>::contains_key(user, role_id)
+                && AssignmentActiveFrom::<T>::get(user, role_id).map_or(true, |from| from <= now)
+                && AssignmentActiveTo::<T>::get(user, role_id).map_or(true, |to| now <= to)
+                && Self::is_role_active(role_id)
+        }
+
+        /// Check the storage invariants: granters of every role are unique, role accounts are indexed
+        /// and role counts of the accounts match their assignments
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn do_try_state() -> Result<(), TryRuntimeError> {
+            for role in Roles::<T>::iter_values() {
+                let granters = role.granters.as_slice();
+                let duplicated = granters
+                    .iter()
+                    .enumerate()
+                    .any(|(i, granter)| granters[..i].contains(granter));
+                if duplicated {
+                    Err("role has duplicated granters")?
+                }
+            }
+            let indexed = Assignments::<T>::iter_keys()
+                .chain(Denials::<T>::iter_keys())
+                .chain(LastRevoked::<T>::iter_keys())
+                .all(|(user, role_id)| RoleAccounts::<T>::contains_key(role_id, user));
+            if !indexed {
+                Err("role account is not indexed")?
+            }
+            Self::ensure_account_role_counts()
+        }
+
+        /// Check that `AccountRoleCount` of every account matches its assignments
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn ensure_account_role_counts() -> Result<(), TryRuntimeError> {
+            for (user, count) in AccountRoleCount::<T>::iter() {
+                if Assignments::<T>::iter_key_prefix(&user).count() as u32 != count {
+                    Err("account role count doesn't match the assignments")?
+                }
+            }
+            for user in Assignments::<T>::iter_keys().map(|(user, _)| user) {
+                if !AccountRoleCount::<T>::contains_key(&user) {
+                    Err("account with roles has no role count")?
+                }
+            }
+            Ok(())
+        }
+
+        /// Get the audit log from the oldest entry to the latest one
+        pub fn audit_log() -> Vec<AuditEntryOf<T>> {
+            let size = T::AuditLogSize::get();
+            let head = AuditLogHead::<T>::get();
+            (head..size)
+                .chain(0..head)
+                .filter_map(AuditLog::<T>::get)
+                .collect()
+        }
+
+        /// Record the action in the audit log, overwriting the oldest entry if it is full
+        fn record_audit(
+            actor: &T::AccountId,
+            user: &T::AccountId,
+            role_id: T::RoleId,
+            action: AuditAction,
+        ) {
+            let size = T::AuditLogSize::get();
+            if size == 0 {
+                return;
+            }
+            let head = AuditLogHead::<T>::get() % size;
+            AuditLog::<T>::insert(
+                head,
+                AuditEntry {
+                    block: frame_system::Pallet::<T>::block_number(),
+                    actor: actor.clone(),
+                    user: user.clone(),
+                    role_id,
+                    action,
+                },
+            );
+            AuditLogHead::<T>::put((head + 1) % size);
+        }
+
+        /// Authorize the user against an empty role list as configured by `EmptyRolesAllow`
+        fn authorize_empty(user: &T::AccountId) -> bool {
+            let authorized = T::EmptyRolesAllow::get();
+            Self::note_authorize(user, &[], authorized);
+            authorized
+        }
+
+        /// Log the authorization check and count it if `TrackAuthorizeMetrics` is set
+        fn note_authorize(user: &T::AccountId, roles: &[T::RoleId], authorized: bool) {
+            frame_support::log::trace!(
+                target: LOG_TARGET,
+                "authorize {:?} against {:?}: {}",
+                user,
+                roles,
+                authorized,
+            );
+            if !T::TrackAuthorizeMetrics::get() {
+                return;
+            }
+            AuthorizeCalls::<T>::mutate(|calls| *calls = calls.saturating_add(1));
+            if authorized {
+                AuthorizeSuccesses::<T>::mutate(|successes| {
+                    *successes = successes.saturating_add(1)
+                });
+            }
+        }
+
+        /// Check that any of the roles is explicitly denied to the user
+        fn is_any_denied(user: &T::AccountId, roles: &[T::RoleId]) -> bool {
+            roles.iter().any(|role| Denials::<T>::get(user, role))
+        }
+
+        /// Check that the role exists and the current block is inside its active window
+        fn is_role_active(role_id: &T::RoleId) -> bool {
+            let Some(role) = Roles::<T>::get(role_id) else {
+                return false;
+            };
+            let now = frame_system::Pallet::<T>::block_number();
+            role.active_from.map_or(true, |from| from <= now)
+                && role.active_to.map_or(true, |to| now <= to)
+        }
+    }
+
+    impl<T: Config> Authorize<T::AccountId, T::RoleId> for Pallet<T> {
+        fn authorize(user: &T::AccountId, roles: &[T::RoleId]) -> bool {
+            if roles.is_empty() {
+                return Self::authorize_empty(user);
+            }
+            let authorized = !SuspendedAccounts::<T>::get(user)
+                && !Self::is_any_denied(user, roles)
+                && roles.iter().any(|role| Self::holds_active_role(user, role));
+            Self::note_authorize(user, roles, authorized);
+            authorized
+        }
+
+        fn authorize_ordered(
+            user: &T::AccountId,
+            roles: &[T::RoleId],
+            priority: &[T::RoleId],
+        ) -> bool {
+            if roles.is_empty() {
+                return Self::authorize_empty(user);
+            }
+            let authorized = !SuspendedAccounts::<T>::get(user)
+                && !Self::is_any_denied(user, roles)
+                && priority
+                    .iter()
+                    .filter(|role| roles.contains(role))
+                    .chain(roles.iter().filter(|role| !priority.contains(role)))
+                    .any(|role| Self::holds_active_role(user, role));
+            Self::note_authorize(user, roles, authorized);
+            authorized
+        }
+
+        fn authorize_mask(user: &T::AccountId, roles: &[T::RoleId]) -> u64 {
+            let mask = if SuspendedAccounts::<T>::get(user) {
+                0
+            } else {
+                roles
+                    .iter()
+                    .take(u64::BITS as usize)
+                    .enumerate()
+                    .filter(|(_, role)| {
+                        !Denials::<T>::get(user, role) && Self::holds_active_role(user, role)
+                    })
+                    .fold(0, |mask, (i, _)| mask | 1 << i)
+            };
+            Self::note_authorize(user, roles, mask != 0);
+            mask
+        }
+
+        fn authorize_first_match<I: Iterator<Item = T::RoleId>>(
+            user: &T::AccountId,
+            roles: I,
+        ) -> Option<T::RoleId> {
+            let mut roles = roles;
+            let matched = if SuspendedAccounts::<T>::get(user) {
+                None
+            } else {
+                roles.find(|role| {
+                    !Denials::<T>::get(user, role) && Self::holds_active_role(user, role)
+                })
+            };
+            let checked = matched
+                .as_ref()
+                .map(core::slice::from_ref)
+                .unwrap_or_default();
+            Self::note_authorize(user, checked, matched.is_some());
+            matched
+        }
+    }
+
+    impl<T: Config> AuthorizePermission<T::AccountId, T::PermissionId> for Pallet<T> {
+        fn authorize_permission(user: &T::AccountId, permission: &T::PermissionId) -> bool {
+            let matched = if SuspendedAccounts::<T>::get(user) {
+                None
+            } else {
+                Assignments::<T>::iter_key_prefix(user).find(|role_id| {
+                    RolePermissions::<T>::get(role_id).contains(permission)
+                        && !Denials::<T>::get(user, role_id)
+                        && Self::holds_active_role(user, role_id)
+                })
+            };
+            let checked = matched
+                .as_ref()
+                .map(core::slice::from_ref)
+                .unwrap_or_default();
+            Self::note_authorize(user, checked, matched.is_some());
+            matched.is_some()
+        }
+    }
+
+    impl<T: Config> AuthorizeByName<T::AccountId> for Pallet<T> {
+        fn authorize_name_prefix(user: &T::AccountId, prefix: &[u8]) -> bool {
+            let roles: Vec<_> = RoleNameIndex::<T>::iter_keys()
+                .filter(|(name, _)| name.starts_with(prefix))
+                .map(|(_, role_id)| role_id)
+                .take(T::MaxPrefixMatches::get() as usize)
+                .collect();
+            !roles.is_empty() && Self::authorize(user, &roles)
+        }
+    }
+
+    impl<T: Config> GranterCount<T::RoleId> for Pallet<T> {
+        fn granter_count(role: &T::RoleId) -> u32 {
+            Roles::<T>::get(role).map_or(0, |role| role.granters.len() as u32)
+        }
+    }
+
+    impl<T: Config> AddRole<T::RoleId> for Pallet<T> {
+        fn add_role(
+            name: &[u8],
+            granters: &[T::RoleId],
+            can_assign_itself: bool,
+        ) -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let next_id = Self::next_role_id()?;
+            Self::create_role(next_id, name, granters, can_assign_itself, None, false)
+        }
+    }
+
+    impl<T: Config> AddOwnedRole<T::AccountId, T::RoleId> for Pallet<T> {
+        fn add_owned_role(
+            owner: T::AccountId,
+            name: &[u8],
+            granters: &[T::RoleId],
+            can_assign_itself: bool,
+        ) -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let next_id = Self::next_role_id()?;
+            Self::create_role(
+                next_id,
+                name,
+                granters,
+                can_assign_itself,
+                Some(owner),
+                false,
+            )
+        }
+    }
+
+    impl<T: Config> ReserveIdRange<T::RoleId> for Pallet<T>
+    where
+        T::RoleId: TryFrom<u128>,
+    {
+        fn reserve_id_range(start: u128, end: u128) -> Result<(), InterfaceError<T::RoleId>> {
+            if start > end {
+                Err(InterfaceError::InvalidRange)?
+            }
+            let (Ok(start_id), Ok(end_id)) = (T::RoleId::try_from(start), T::RoleId::try_from(end))
+            else {
+                Err(InterfaceError::InvalidRange)?
+            };
+            let overlaps = IdRanges::<T>::iter()
+                .any(|(other_start, (other_end, _))| start <= other_end && other_start <= end);
+            if overlaps {
+                Err(InterfaceError::InvalidRange)?
+            }
+
+            IdRanges::<T>::insert(start, (end, start));
+            ReservedIds::<T>::insert(start_id, (end_id, end));
+            Ok(())
+        }
+
+        fn add_role_in_range(
+            range_start: u128,
+            name: &[u8],
+            granters: &[T::RoleId],
+            can_assign_itself: bool,
+        ) -> Result<T::RoleId, InterfaceError<T::RoleId>> {
+            let Some((end, mut next)) = IdRanges::<T>::get(range_start) else {
+                Err(InterfaceError::InvalidRange)?
+            };
+
+            let id = loop {
+                if next > end {
+                    Err(InterfaceError::RangeExhausted)?
+                }
+                let id = T::RoleId::try_from(next).map_err(|_| InterfaceError::RangeExhausted)?;
+                next += 1;
+                if !Roles::<T>::contains_key(id) {
+                    break id;
+                }
+            };
+            IdRanges::<T>::insert(range_start, (end, next));
+
+            Self::create_role(id, name, granters, can_assign_itself, None, false)
+        }
+    }
+
+    impl<T: Config> From<InterfaceError<T::RoleId>> for Error<T> {
+        fn from(error: InterfaceError<T::RoleId>) -> Self {
+            match error {
+                InterfaceError::NotAuthorized | InterfaceError::NotSelfAssignable { .. } => {
+                    Error::<T>::NotAuthorized
+                }
+                InterfaceError::RoleNotExist { .. } => Error::<T>::RoleNotExist,
+                InterfaceError::NameTooLong { .. } => Error::<T>::NameTooLong,
+                InterfaceError::GrantersListTooLong { .. } => Error::<T>::TooManyGranters,
+                InterfaceError::IdsExhausted
+                | InterfaceError::InvalidRange
+                | InterfaceError::RangeExhausted => Error::<T>::IdsExhausted,
+                InterfaceError::InvalidGrantee => Error::<T>::InvalidGrantee,
+                InterfaceError::TooManyRolesForAccount { .. } => Error::<T>::TooManyRolesForAccount,
+            }
+        }
+    }
+
+    impl<T: Config> PreassignRole<T::AccountId, T::RoleId> for Pallet<T> {
+        fn preassign_role(
+            user: T::AccountId,
+            role: T::RoleId,
+        ) -> Result<(), InterfaceError<T::RoleId>> {
             if !Roles::<T>::contains_key(role) {
-                Err(InterfaceError::RoleNotExist)?
+                Err(InterfaceError::RoleNotExist { role })?
             };
+            Self::ensure_valid_grantee(&user).map_err(|_| InterfaceError::InvalidGrantee)?;
+            if !Self::can_hold_role(&user, role) {
+                Err(InterfaceError::TooManyRolesForAccount {
+                    max: T::MaxRolesPerAccount::get(),
+                })?
+            }
 
-            Assignments::<T>::set(user, role, true);
+            Self::assign(&user, role, None);
 
             Ok(())
         }
@@ -0,0 +1,36 @@
+//! Macros for the consumer pallets
+
+/// Items used by the expanded macros, so the consumer doesn't need them in scope
+#[doc(hidden)]
+pub mod __private {
+    pub use frame_support::traits::Get;
+    pub use frame_system::ensure_signed;
+}
+
+/// Run the body of an extrinsic only if the signer of the origin holds the configured role
+///
+/// The signer is bound to the given name inside the body. Unsigned origins fail with `BadOrigin`,
+/// signers without the role fail with the given error, e.g. the consumer's `NotAuthorized`.
+/// The role is read from a `Get` getter, e.g. a type set in the consumer's config.
+///
+/// ```ignore
+/// #[pallet::call_index(0)]
+/// #[pallet::weight(0)]
+/// pub fn gated_call(origin: OriginFor<T>) -> DispatchResult {
+///     pallet_rbac::role_gated!(origin, T::RBAC, T::AdminRole, Error::<T>::NotAuthorized, |who| {
+///         LastCaller::<T>::put(who);
+///         Ok(())
+///     })
+/// }
+/// ```
+#[macro_export]
+macro_rules! role_gated {
+    ($origin:expr, $rbac:ty, $role:ty, $error:expr, |$who:ident| $body:block) => {{
+        let $who = $crate::__private::ensure_signed($origin)?;
+        let role = <$role as $crate::__private::Get<_>>::get();
+        if !<$rbac as $crate::Authorize<_, _>>::authorize(&$who, &[role]) {
+            return Err($error.into());
+        }
+        $body
+    }};
+}
@@ -0,0 +1,119 @@
+//! Binary Merkle tree over the role assignments
+//!
+//! Light clients verify the membership proofs against `MembershipRoot` without the full state.
+//! Leaves are the hashes of the `(account, role)` pairs in the sorted order of the pairs,
+//! a node without a pair is promoted to the next level as is.
+//! Leaf, node and root preimages are prefixed with distinct tags, so a node can't be passed off as a leaf,
+//! and the root commits to the number of the leaves the proofs are checked against.
+
+use codec::{Decode, Encode};
+use frame_support::{sp_runtime::traits::Hash, sp_std::vec::Vec};
+use scale_info::TypeInfo;
+
+/// Proof that the assignment is one of the leaves of the tree
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq, TypeInfo)]
+pub struct MembershipProof<H> {
+    /// Position of the leaf in the tree
+    pub leaf_index: u32,
+    /// Number of the leaves in the tree
+    pub leaf_count: u32,
+    /// Hashes of the sibling nodes from the leaf level up to the root
+    pub siblings: Vec<H>,
+}
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+const ROOT_TAG: u8 = 0x02;
+
+/// Hash the assignment into a leaf
+pub fn leaf_hash<H: Hash, AId: Encode, RId: Encode>(user: &AId, role_id: &RId) -> H::Output {
+    H::hash_of(&(LEAF_TAG, user, role_id))
+}
+
+fn node_hash<H: Hash>(left: &H::Output, right: &H::Output) -> H::Output {
+    H::hash_of(&(NODE_TAG, left, right))
+}
+
+/// Bind the number of the leaves into the root, so a proof can't claim another tree shape
+fn root_hash<H: Hash>(top: &H::Output, leaf_count: u32) -> H::Output {
+    H::hash_of(&(ROOT_TAG, leaf_count, top))
+}
+
+fn next_level<H: Hash>(level: &[H::Output]) -> Vec<H::Output> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash::<H>(left, right),
+            _ => pair[0],
+        })
+        .collect()
+}
+
+/// Compute the root of the tree, the default hash if there are no leaves
+pub fn root<H: Hash>(leaves: &[H::Output]) -> H::Output {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level::<H>(&level);
+    }
+    level
+        .first()
+        .map(|top| root_hash::<H>(top, leaves.len() as u32))
+        .unwrap_or_default()
+}
+
+/// Build the proof of the leaf at `index`, `None` if there is no such leaf
+pub fn proof<H: Hash>(leaves: &[H::Output], index: usize) -> Option<MembershipProof<H::Output>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(position ^ 1) {
+            siblings.push(*sibling);
+        }
+        level = next_level::<H>(&level);
+        position /= 2;
+    }
+
+    Some(MembershipProof {
+        leaf_index: index as u32,
+        leaf_count: leaves.len() as u32,
+        siblings,
+    })
+}
+
+/// Check that the leaf is included in the tree with the given root
+pub fn verify<H: Hash>(
+    root: &H::Output,
+    leaf: H::Output,
+    proof: &MembershipProof<H::Output>,
+) -> bool {
+    if proof.leaf_index >= proof.leaf_count {
+        return false;
+    }
+
+    let mut siblings = proof.siblings.iter();
+    let mut hash = leaf;
+    let mut position = proof.leaf_index;
+    let mut count = proof.leaf_count;
+    while count > 1 {
+        if position % 2 == 1 {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            hash = node_hash::<H>(sibling, &hash);
+        } else if position + 1 < count {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            hash = node_hash::<H>(&hash, sibling);
+        }
+        position /= 2;
+        count = (count + 1) / 2;
+    }
+
+    siblings.next().is_none() && root_hash::<H>(&hash, proof.leaf_count) == *root
+}
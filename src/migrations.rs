@@ -0,0 +1,442 @@
+//! Storage migrations of the pallet
+
+use crate::{
+    AccountRoleCount, AssignmentInfo, Assignments, Config, Denials, HolderCount, IdRanges,
+    LastRevoked, Pallet, ReservedIds, RoleAccounts, RoleNameIndex, Roles,
+};
+#[cfg(feature = "try-runtime")]
+use frame_support::sp_runtime::TryRuntimeError;
+use frame_support::{
+    pallet_prelude::*,
+    sp_std::{marker::PhantomData, vec::Vec},
+    storage_alias,
+    traits::OnRuntimeUpgrade,
+};
+
+/// Storage layout of the version 0
+pub mod v0 {
+    use super::*;
+
+    /// Role as it was stored in the storage version 0
+    #[derive(Clone, Debug, Decode, Encode, PartialEq)]
+    pub struct RoleInfo<RId, LN: Get<u32>, LG: Get<u32>> {
+        /// Role name
+        pub name: BoundedVec<u8, LN>,
+        /// Role granters, possibly duplicated
+        pub granters: BoundedVec<RId, LG>,
+    }
+
+    /// Role as it was stored by the pallet in the storage version 0
+    pub type RoleInfoOf<T> = RoleInfo<
+        <T as Config>::RoleId,
+        <T as Config>::NameMaxLength,
+        <T as Config>::GrantersListMaxLength,
+    >;
+
+    /// `Roles` as they were stored in the storage version 0
+    #[storage_alias]
+    pub type Roles<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, <T as Config>::RoleId, RoleInfoOf<T>>;
+}
+
+/// Migration to the storage version 1
+pub mod v1 {
+    use super::*;
+    use frame_support::sp_runtime::traits::Zero;
+
+    /// Convert the roles of the storage version 0 into the current `RoleInfo` layout
+    /// and remove duplicated granters left by the earlier versions of `add_role`.
+    ///
+    /// The version 0 roles have only a name and granters. The migrated roles have no active window,
+    /// no conflicts and no owner, and their `modified_at` is zero, as the real block is unknown.
+    /// The order of granters is preserved, only the first entry of each granter is kept.
+    ///
+    /// Add it to the runtime's `Executive` migrations:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (pallet_rbac::migrations::v1::DedupGranters<Runtime>,);
+    /// ```
+    pub struct DedupGranters<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for DedupGranters<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0_u64;
+            Roles::<T>::translate_values(|old: v0::RoleInfoOf<T>| {
+                translated += 1;
+                let mut granters = old.granters;
+                let mut unique: Vec<T::RoleId> = Vec::with_capacity(granters.len());
+                granters.retain(|granter| {
+                    let is_new = !unique.contains(granter);
+                    if is_new {
+                        unique.push(*granter);
+                    }
+                    is_new
+                });
+                Some(crate::RoleInfo {
+                    name: old.name,
+                    granters,
+                    active_from: None,
+                    active_to: None,
+                    conflicts: BoundedVec::default(),
+                    modified_at: Zero::zero(),
+                    owner: None,
+                })
+            });
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+            // the keys are counted without decoding, so the roles of any layout are included
+            Ok((Roles::<T>::iter_keys().count() as u32).encode())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            let before = u32::decode(&mut &state[..]).map_err(|_| "invalid pre_upgrade state")?;
+            // `translate_values` removes the roles it can't decode, so a lost role means a wrong layout
+            let decoded = Roles::<T>::iter_values().count() as u32;
+            ensure!(decoded == before, "roles were lost by the migration");
+            ensure!(
+                StorageVersion::get::<Pallet<T>>() >= 1,
+                "storage version is not updated"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Migration to the storage version 2
+pub mod v2 {
+    use super::*;
+    use frame_support::sp_runtime::traits::Zero;
+    use frame_system::pallet_prelude::BlockNumberFor;
+
+    /// Assignment as it was stored in the storage version 2
+    #[derive(Clone, Copy, Debug, Decode, Encode, Eq, MaxEncodedLen, PartialEq, TypeInfo)]
+    pub struct AssignmentInfo<BN> {
+        /// Block at which the role was granted
+        pub granted_at: BN,
+    }
+
+    /// `Assignments` as they were stored in the storage version 2
+    #[storage_alias]
+    pub type Assignments<T: Config> = StorageDoubleMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as frame_system::Config>::AccountId,
+        Blake2_128Concat,
+        <T as Config>::RoleId,
+        AssignmentInfo<BlockNumberFor<T>>,
+    >;
+
+    /// Convert the `bool` flags of `Assignments` into `AssignmentInfo` entries.
+    /// `true` flags become assignments made at block zero, as the real block is unknown,
+    /// `false` flags are removed, so a missing entry still means the role is not assigned.
+    ///
+    /// Run it after `v1::DedupGranters` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v1::DedupGranters<Runtime>,
+    ///     pallet_rbac::migrations::v2::AssignmentsToOptionQuery<Runtime>,
+    /// );
+    /// ```
+    pub struct AssignmentsToOptionQuery<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for AssignmentsToOptionQuery<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 2 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0_u64;
+            Assignments::<T>::translate::<bool, _>(|_, _, assigned| {
+                translated += 1;
+                assigned.then(|| AssignmentInfo {
+                    granted_at: Zero::zero(),
+                })
+            });
+            StorageVersion::new(2).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Migration to the storage version 3
+pub mod v3 {
+    use super::*;
+    use frame_system::pallet_prelude::BlockNumberFor;
+
+    /// Add the extrinsic index to the `Assignments` entries.
+    /// The index of the existing assignments is unknown, so it is set to `None`.
+    ///
+    /// Run it after `v2::AssignmentsToOptionQuery` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v2::AssignmentsToOptionQuery<Runtime>,
+    ///     pallet_rbac::migrations::v3::AssignmentExtrinsicIndex<Runtime>,
+    /// );
+    /// ```
+    pub struct AssignmentExtrinsicIndex<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for AssignmentExtrinsicIndex<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 3 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0_u64;
+            Assignments::<T>::translate::<super::v2::AssignmentInfo<BlockNumberFor<T>>, _>(
+                |_, _, old| {
+                    translated += 1;
+                    Some(AssignmentInfo {
+                        granted_at: old.granted_at,
+                        extrinsic_index: None,
+                    })
+                },
+            );
+            StorageVersion::new(3).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Migration to the storage version 4
+pub mod v4 {
+    use super::*;
+
+    /// Index the existing assignments, denials and revocations by role in `RoleAccounts`,
+    /// so `purge_role` can find them without scanning the storage.
+    ///
+    /// Run it after `v3::AssignmentExtrinsicIndex` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v3::AssignmentExtrinsicIndex<Runtime>,
+    ///     pallet_rbac::migrations::v4::IndexRoleAccounts<Runtime>,
+    /// );
+    /// ```
+    pub struct IndexRoleAccounts<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for IndexRoleAccounts<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 4 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut indexed = 0_u64;
+            for (user, role_id) in Assignments::<T>::iter_keys()
+                .chain(Denials::<T>::iter_keys())
+                .chain(LastRevoked::<T>::iter_keys())
+            {
+                indexed += 1;
+                RoleAccounts::<T>::insert(role_id, user, ());
+            }
+            StorageVersion::new(4).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(indexed + 1, indexed + 1)
+        }
+    }
+}
+
+/// Migration to the storage version 5
+pub mod v5 {
+    use super::*;
+
+    /// Count the existing assignments of every role in `HolderCount`.
+    /// Without it the roles granted before the counter have no holders, so a protected role
+    /// can't be revoked at all and `RoleGranted::total_holders` is wrong.
+    ///
+    /// Run it after `v4::IndexRoleAccounts` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v4::IndexRoleAccounts<Runtime>,
+    ///     pallet_rbac::migrations::v5::BackfillHolderCount<Runtime>,
+    /// );
+    /// ```
+    pub struct BackfillHolderCount<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for BackfillHolderCount<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 5 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            // the counters written after the upgrade are recounted, so nothing is counted twice
+            let cleared = HolderCount::<T>::clear(u32::MAX, None).unique as u64;
+            let mut counted = 0_u64;
+            for (_, role_id) in Assignments::<T>::iter_keys() {
+                counted += 1;
+                HolderCount::<T>::mutate(role_id, |count| *count = count.saturating_add(1));
+            }
+            StorageVersion::new(5).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(2 * counted + 1, counted + cleared + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            for role_id in Roles::<T>::iter_keys() {
+                let holders = Assignments::<T>::iter_keys()
+                    .filter(|(_, role)| *role == role_id)
+                    .count() as u32;
+                ensure!(
+                    HolderCount::<T>::get(role_id) == holders,
+                    "holder count doesn't match the assignments"
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Migration to the storage version 6
+pub mod v6 {
+    use super::*;
+
+    /// Count the existing assignments of every account in `AccountRoleCount`.
+    /// Without it the accounts holding roles granted before the counter can go past `MaxRolesPerAccount`.
+    ///
+    /// Run it after `v5::BackfillHolderCount` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v5::BackfillHolderCount<Runtime>,
+    ///     pallet_rbac::migrations::v6::BackfillAccountRoleCount<Runtime>,
+    /// );
+    /// ```
+    pub struct BackfillAccountRoleCount<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for BackfillAccountRoleCount<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 6 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            // the counters written after the upgrade are recounted, so nothing is counted twice
+            let cleared = AccountRoleCount::<T>::clear(u32::MAX, None).unique as u64;
+            let mut counted = 0_u64;
+            for (user, _) in Assignments::<T>::iter_keys() {
+                counted += 1;
+                AccountRoleCount::<T>::mutate(user, |count| *count = count.saturating_add(1));
+            }
+            StorageVersion::new(6).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(2 * counted + 1, counted + cleared + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            Pallet::<T>::ensure_account_role_counts()
+        }
+    }
+}
+
+/// Migration to the storage version 7
+pub mod v7 {
+    use super::*;
+
+    /// Index the names of the existing roles in `RoleNameIndex`, so the roles created before the index
+    /// are found by `authorize_name_prefix` and the name lookups.
+    ///
+    /// Run it after `v6::BackfillAccountRoleCount` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v6::BackfillAccountRoleCount<Runtime>,
+    ///     pallet_rbac::migrations::v7::IndexRoleNames<Runtime>,
+    /// );
+    /// ```
+    pub struct IndexRoleNames<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for IndexRoleNames<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 7 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut indexed = 0_u64;
+            for (role_id, role) in Roles::<T>::iter() {
+                indexed += 1;
+                RoleNameIndex::<T>::insert(role.name, role_id, ());
+            }
+            StorageVersion::new(7).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(indexed + 1, indexed + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            for (role_id, role) in Roles::<T>::iter() {
+                ensure!(
+                    RoleNameIndex::<T>::contains_key(role.name, role_id),
+                    "role name is not indexed"
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Migration to the version 8
+pub mod v8 {
+    use super::*;
+
+    /// Copy the reserved id ranges to `ReservedIds`, so `add_role` skips the ranges reserved before it.
+    /// Runtimes whose `RoleId` can't be built from `u128` have no reserved ranges and don't need it.
+    ///
+    /// Run it after `v7::IndexRoleNames` if the chain hasn't run that one yet:
+    ///
+    /// ```ignore
+    /// pub type Migrations = (
+    ///     pallet_rbac::migrations::v7::IndexRoleNames<Runtime>,
+    ///     pallet_rbac::migrations::v8::IndexReservedIds<Runtime>,
+    /// );
+    /// ```
+    pub struct IndexReservedIds<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for IndexReservedIds<T>
+    where
+        T::RoleId: TryFrom<u128>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() >= 8 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut ranges = 0_u64;
+            for (start, (end, _)) in IdRanges::<T>::iter() {
+                ranges += 1;
+                // the ranges are checked to fit `RoleId` when they are reserved
+                if let (Ok(start_id), Ok(end_id)) =
+                    (T::RoleId::try_from(start), T::RoleId::try_from(end))
+                {
+                    ReservedIds::<T>::insert(start_id, (end_id, end));
+                }
+            }
+            StorageVersion::new(8).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(ranges + 1, ranges + 1)
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+            ensure!(
+                ReservedIds::<T>::iter().count() == IdRanges::<T>::iter().count(),
+                "reserved ranges are not indexed"
+            );
+            Ok(())
+        }
+    }
+}
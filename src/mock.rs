@@ -1,5 +1,9 @@
 use crate as pallet_rbac;
-use frame_support::traits::{ConstU16, ConstU64};
+use core::cell::RefCell;
+use frame_support::{
+    parameter_types,
+    traits::{ConstBool, ConstU16, ConstU64, ConstU8, Get},
+};
 use sp_core::{ConstU32, H256};
 use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup},
@@ -14,6 +18,7 @@ frame_support::construct_runtime!(
     {
         System: frame_system,
         RBACModule: pallet_rbac,
+        Consumer: consumer,
     }
 );
 
@@ -43,16 +48,200 @@ impl frame_system::Config for Test {
     type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+thread_local! {
+    /// Roles reported as removed through `OnRoleChanged`
+    pub static REMOVED_ROLES: RefCell<Vec<u32>> = RefCell::new(vec![]);
+}
+
+pub fn removed_roles() -> Vec<u32> {
+    REMOVED_ROLES.with(|roles| roles.borrow().clone())
+}
+
+thread_local! {
+    /// Results of the revocations attempted by `RoleChangeRecorder` from inside the notification
+    pub static REENTRANT_REVOCATIONS: RefCell<Vec<bool>> = RefCell::new(vec![]);
+}
+
+pub fn reentrant_revocations() -> Vec<bool> {
+    REENTRANT_REVOCATIONS.with(|results| results.borrow().clone())
+}
+
+parameter_types! {
+    /// `(revoker, holder)` for `RoleChangeRecorder` to revoke the removed role from the holder, breaking the contract
+    pub static ReenterOnRoleRemoved: Option<(u64, u64)> = None;
+}
+
+/// Records the removed roles
+pub struct RoleChangeRecorder;
+
+impl pallet_rbac::OnRoleChanged<u32> for RoleChangeRecorder {
+    fn on_role_removed(role: &u32) {
+        REMOVED_ROLES.with(|roles| roles.borrow_mut().push(*role));
+        if let Some((revoker, holder)) = ReenterOnRoleRemoved::get() {
+            let result = RBACModule::revoke_role(RuntimeOrigin::signed(revoker), holder, *role);
+            REENTRANT_REVOCATIONS.with(|results| results.borrow_mut().push(result.is_ok()));
+        }
+    }
+}
+
 type RoleId = u32;
 pub type NameMaxLength = ConstU32<20>;
 pub type GrantersListMaxLength = ConstU32<20>;
+pub type BatchMaxLength = ConstU32<10>;
+pub type MaxFailedAttempts = ConstU32<3>;
+pub type ResetWindow = ConstU64<10>;
+pub type MaxPurge = ConstU32<5>;
+pub type MaxPrefixMatches = ConstU32<3>;
+pub type MaxConflicts = ConstU32<3>;
+pub type UriMaxLength = ConstU32<8>;
+pub type MaxDependencies = ConstU32<2>;
+pub type MaxFreedIds = ConstU32<2>;
+pub type MaxMetadataKeyLength = ConstU32<8>;
+pub type MaxMetadataValueLength = ConstU32<16>;
+pub type MaxMetadataPerRole = ConstU32<2>;
+pub type MaxPermissionsPerRole = ConstU32<2>;
+pub type AuditLogSize = ConstU32<3>;
+pub type MaxRolesPerAccount = ConstU32<4>;
+
+parameter_types! {
+    pub static RejectDefaultAccount: bool = false;
+    pub static TrackAuthorizeMetrics: bool = false;
+    pub static TrackMembershipRoot: bool = false;
+    pub static MaxMembershipSize: u32 = 16;
+    pub static RoleIdStart: u32 = 0;
+    pub static FlapCooldown: u64 = 0;
+    pub static EmptyRolesAllow: bool = false;
+    pub static RecycleRoleIds: bool = false;
+}
 
 impl pallet_rbac::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
     type RoleId = RoleId;
+    type IdAllocator = pallet_rbac::IncrementableIds;
+    type RoleIdStart = RoleIdStart;
+    type RecycleRoleIds = RecycleRoleIds;
+    type MaxFreedIds = MaxFreedIds;
     type GrantersListMaxLength = GrantersListMaxLength;
     type NameMaxLength = NameMaxLength;
+    type BatchMaxLength = BatchMaxLength;
+    type ProtectOrigin = frame_system::EnsureRoot<u64>;
+    type MaxFailedAttempts = MaxFailedAttempts;
+    type ResetWindow = ResetWindow;
+    type FlapCooldown = FlapCooldown;
+    type ForceOrigin = frame_system::EnsureRoot<u64>;
+    type MaxPurge = MaxPurge;
+    type OnRoleChanged = RoleChangeRecorder;
+    type MaxPrefixMatches = MaxPrefixMatches;
+    type SuspendOrigin = frame_system::EnsureRoot<u64>;
+    type MaxConflicts = MaxConflicts;
+    type RejectDefaultAccount = RejectDefaultAccount;
+    type EmptyRolesAllow = EmptyRolesAllow;
+    type TrackAuthorizeMetrics = TrackAuthorizeMetrics;
+    type TrackMembershipRoot = TrackMembershipRoot;
+    type MaxMembershipSize = MaxMembershipSize;
+    type MaxRolesPerAccount = MaxRolesPerAccount;
+    type AuditLogSize = AuditLogSize;
+    type PermissionId = u32;
+    type MaxPermissionsPerRole = MaxPermissionsPerRole;
+    type MaxMetadataKeyLength = MaxMetadataKeyLength;
+    type MaxMetadataValueLength = MaxMetadataValueLength;
+    type MaxMetadataPerRole = MaxMetadataPerRole;
+    type MaxDependencies = MaxDependencies;
+    type UriMaxLength = UriMaxLength;
+}
+
+/// Consumer pallet with calls restricted to the holders of a role
+#[frame_support::pallet]
+pub mod consumer {
+    use frame_support::{dispatch::PostDispatchInfo, pallet_prelude::*};
+    use frame_system::pallet_prelude::*;
+    use sp_runtime::traits::Dispatchable;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type RuntimeCall: Parameter
+            + Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>;
+        type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+        type RoleId: Parameter;
+        type RBAC: crate::Authorize<Self::AccountId, Self::RoleId>;
+        type GatedRole: Get<Self::RoleId>;
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The signer doesn't hold the gated role
+        NotAuthorized,
+    }
+
+    /// The last account that called the restricted call
+    #[pallet::storage]
+    pub type LastCaller<T: Config> = StorageValue<_, T::AccountId>;
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        #[pallet::call_index(0)]
+        #[pallet::weight(0)]
+        pub fn restricted(origin: OriginFor<T>) -> DispatchResult {
+            let who = T::AdminOrigin::ensure_origin(origin)?;
+            LastCaller::<T>::put(who);
+            Ok(())
+        }
+
+        #[pallet::call_index(1)]
+        #[pallet::weight(0)]
+        pub fn gated(origin: OriginFor<T>) -> DispatchResult {
+            crate::role_gated!(
+                origin,
+                T::RBAC,
+                T::GatedRole,
+                Error::<T>::NotAuthorized,
+                |who| {
+                    LastCaller::<T>::put(who);
+                    Ok(())
+                }
+            )
+        }
+
+        /// Dispatch the call returning its error, like `utility.batch_all` does
+        #[pallet::call_index(2)]
+        #[pallet::weight(0)]
+        pub fn wrap(
+            origin: OriginFor<T>,
+            call: Box<<T as Config>::RuntimeCall>,
+        ) -> DispatchResultWithPostInfo {
+            call.dispatch(origin)
+        }
+    }
+}
+
+/// Role accepted by the consumer pallet, it is the first generated role id
+pub type ConsumerAdminRole = ConstU32<1>;
+
+impl consumer::Config for Test {
+    type RuntimeCall = RuntimeCall;
+    type AdminOrigin = pallet_rbac::EnsureRoleHolder<Test, ConsumerAdminRole>;
+    type RoleId = u32;
+    type RBAC = RBACModule;
+    type GatedRole = ConsumerAdminRole;
+}
+
+/// Role allowed to dispatch `remark`, it is the first generated role id
+pub type RemarkRole = ConstU32<1>;
+
+/// Restricts `remark` to the `RemarkRole` holders
+pub struct TestCallRoles;
+
+impl pallet_rbac::CallRoleMap<RuntimeCall, u32> for TestCallRoles {
+    fn roles_for(call: &RuntimeCall) -> Option<Vec<u32>> {
+        match call {
+            RuntimeCall::System(frame_system::Call::remark { .. }) => Some(vec![RemarkRole::get()]),
+            _ => None,
+        }
+    }
 }
 
 // Build genesis storage according to the mock runtime.
@@ -62,3 +251,206 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         .unwrap()
         .into()
 }
+
+/// Mock runtime with a role id that doesn't implement `Incrementable`
+pub mod sequential {
+    use super::*;
+    use codec::{Decode, Encode, MaxEncodedLen};
+    use scale_info::TypeInfo;
+    use serde::{Deserialize, Serialize};
+
+    type Block = frame_system::mocking::MockBlock<Test>;
+
+    frame_support::construct_runtime!(
+        pub enum Test
+        {
+            System: frame_system,
+            RBACModule: pallet_rbac,
+        }
+    );
+
+    impl frame_system::Config for Test {
+        type BaseCallFilter = frame_support::traits::Everything;
+        type BlockWeights = ();
+        type BlockLength = ();
+        type DbWeight = ();
+        type RuntimeOrigin = RuntimeOrigin;
+        type RuntimeCall = RuntimeCall;
+        type Nonce = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Block = Block;
+        type RuntimeEvent = RuntimeEvent;
+        type BlockHashCount = ConstU64<250>;
+        type Version = ();
+        type PalletInfo = PalletInfo;
+        type AccountData = ();
+        type OnNewAccount = ();
+        type OnKilledAccount = ();
+        type SystemWeightInfo = ();
+        type SS58Prefix = ConstU16<42>;
+        type OnSetCode = ();
+        type MaxConsumers = frame_support::traits::ConstU32<16>;
+    }
+
+    /// Role id that can be built only from the counter
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        Default,
+        Decode,
+        Deserialize,
+        Encode,
+        Eq,
+        MaxEncodedLen,
+        Ord,
+        PartialEq,
+        PartialOrd,
+        Serialize,
+        TypeInfo,
+    )]
+    pub struct RoleId(pub u8);
+
+    impl TryFrom<u128> for RoleId {
+        type Error = ();
+
+        fn try_from(value: u128) -> Result<Self, Self::Error> {
+            u8::try_from(value).map(RoleId).map_err(|_| ())
+        }
+    }
+
+    impl pallet_rbac::Config for Test {
+        type RuntimeEvent = RuntimeEvent;
+        type WeightInfo = ();
+        type RoleId = RoleId;
+        type IdAllocator = pallet_rbac::SequentialIds;
+        type RoleIdStart = frame_support::traits::GetDefault;
+        type RecycleRoleIds = ConstBool<false>;
+        type MaxFreedIds = MaxFreedIds;
+        type GrantersListMaxLength = GrantersListMaxLength;
+        type NameMaxLength = NameMaxLength;
+        type BatchMaxLength = BatchMaxLength;
+        type ProtectOrigin = frame_system::EnsureRoot<u64>;
+        type MaxFailedAttempts = MaxFailedAttempts;
+        type ResetWindow = ResetWindow;
+        type FlapCooldown = ConstU64<0>;
+        type ForceOrigin = frame_system::EnsureRoot<u64>;
+        type MaxPurge = MaxPurge;
+        type OnRoleChanged = ();
+        type MaxPrefixMatches = MaxPrefixMatches;
+        type SuspendOrigin = frame_system::EnsureRoot<u64>;
+        type MaxConflicts = MaxConflicts;
+        type RejectDefaultAccount = ConstBool<false>;
+        type EmptyRolesAllow = ConstBool<false>;
+        type TrackAuthorizeMetrics = ConstBool<false>;
+        type TrackMembershipRoot = ConstBool<false>;
+        type MaxMembershipSize = ConstU32<16>;
+        type MaxRolesPerAccount = MaxRolesPerAccount;
+        type AuditLogSize = AuditLogSize;
+        type PermissionId = u32;
+        type MaxPermissionsPerRole = MaxPermissionsPerRole;
+        type MaxMetadataKeyLength = MaxMetadataKeyLength;
+        type MaxMetadataValueLength = MaxMetadataValueLength;
+        type MaxMetadataPerRole = MaxMetadataPerRole;
+        type MaxDependencies = MaxDependencies;
+        type UriMaxLength = UriMaxLength;
+    }
+
+    pub fn new_test_ext() -> sp_io::TestExternalities {
+        frame_system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .into()
+    }
+}
+
+/// Mock runtime with a small `Incrementable` role id, to run out of the ids.
+/// Its database is weighted as RocksDb, to compare `SubstrateWeight` with the `()` weights.
+pub mod small {
+    use super::*;
+
+    type Block = frame_system::mocking::MockBlock<Test>;
+
+    frame_support::construct_runtime!(
+        pub enum Test
+        {
+            System: frame_system,
+            RBACModule: pallet_rbac,
+        }
+    );
+
+    impl frame_system::Config for Test {
+        type BaseCallFilter = frame_support::traits::Everything;
+        type BlockWeights = ();
+        type BlockLength = ();
+        type DbWeight = frame_support::weights::constants::RocksDbWeight;
+        type RuntimeOrigin = RuntimeOrigin;
+        type RuntimeCall = RuntimeCall;
+        type Nonce = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Block = Block;
+        type RuntimeEvent = RuntimeEvent;
+        type BlockHashCount = ConstU64<250>;
+        type Version = ();
+        type PalletInfo = PalletInfo;
+        type AccountData = ();
+        type OnNewAccount = ();
+        type OnKilledAccount = ();
+        type SystemWeightInfo = ();
+        type SS58Prefix = ConstU16<42>;
+        type OnSetCode = ();
+        type MaxConsumers = frame_support::traits::ConstU32<16>;
+    }
+
+    type RoleId = u8;
+
+    impl pallet_rbac::Config for Test {
+        type RuntimeEvent = RuntimeEvent;
+        type WeightInfo = ();
+        type RoleId = RoleId;
+        type IdAllocator = pallet_rbac::IncrementableIds;
+        type RoleIdStart = ConstU8<0>;
+        type RecycleRoleIds = ConstBool<false>;
+        type MaxFreedIds = MaxFreedIds;
+        type GrantersListMaxLength = GrantersListMaxLength;
+        type NameMaxLength = NameMaxLength;
+        type BatchMaxLength = BatchMaxLength;
+        type ProtectOrigin = frame_system::EnsureRoot<u64>;
+        type MaxFailedAttempts = MaxFailedAttempts;
+        type ResetWindow = ResetWindow;
+        type FlapCooldown = ConstU64<0>;
+        type ForceOrigin = frame_system::EnsureRoot<u64>;
+        type MaxPurge = MaxPurge;
+        type OnRoleChanged = ();
+        type MaxPrefixMatches = MaxPrefixMatches;
+        type SuspendOrigin = frame_system::EnsureRoot<u64>;
+        type MaxConflicts = MaxConflicts;
+        type RejectDefaultAccount = ConstBool<false>;
+        type EmptyRolesAllow = ConstBool<false>;
+        type TrackAuthorizeMetrics = ConstBool<false>;
+        type TrackMembershipRoot = ConstBool<false>;
+        type MaxMembershipSize = ConstU32<16>;
+        type MaxRolesPerAccount = MaxRolesPerAccount;
+        type AuditLogSize = AuditLogSize;
+        type PermissionId = u32;
+        type MaxPermissionsPerRole = MaxPermissionsPerRole;
+        type MaxMetadataKeyLength = MaxMetadataKeyLength;
+        type MaxMetadataValueLength = MaxMetadataValueLength;
+        type MaxMetadataPerRole = MaxMetadataPerRole;
+        type MaxDependencies = MaxDependencies;
+        type UriMaxLength = UriMaxLength;
+    }
+
+    pub fn new_test_ext() -> sp_io::TestExternalities {
+        frame_system::GenesisConfig::<Test>::default()
+            .build_storage()
+            .unwrap()
+            .into()
+    }
+}
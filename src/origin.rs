@@ -0,0 +1,112 @@
+use crate::{Authorize, Config, Origin, Pallet};
+use frame_support::{
+    sp_std::marker::PhantomData,
+    traits::{EnsureOrigin, EnsureOriginWithArg, Get},
+};
+use frame_system::RawOrigin;
+
+/// Ensure that the origin is signed by an account holding role `R`
+/// or is a `RoleHolder` origin of this role.
+///
+/// Use it in the consumer's `Config` instead of `ensure_signed` and a manual `authorize` call:
+///
+/// ```ignore
+/// type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+/// ```
+///
+/// The role is checked once again for the `RoleHolder` origin, so it can't outlive the assignment.
+pub struct EnsureRoleHolder<T, R>(PhantomData<(T, R)>);
+
+impl<T, R, O> EnsureOrigin<O> for EnsureRoleHolder<T, R>
+where
+    T: Config,
+    R: Get<T::RoleId>,
+    O: Into<Result<Origin<T>, O>>
+        + From<Origin<T>>
+        + Into<Result<RawOrigin<T::AccountId>, O>>
+        + From<RawOrigin<T::AccountId>>,
+{
+    type Success = T::AccountId;
+
+    fn try_origin(o: O) -> Result<Self::Success, O> {
+        try_role_holder::<T, O>(o, R::get())
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin() -> Result<O, ()> {
+        successful_role_holder::<T, O>(R::get())
+    }
+}
+
+/// Ensure that the origin is signed by an account holding the role passed as the argument
+/// or is a `RoleHolder` origin of this role.
+///
+/// Same as `EnsureRoleHolder`, but the role is chosen by the caller, e.g. the role guarding a collection:
+///
+/// ```ignore
+/// type CollectionOrigin: EnsureOriginWithArg<Self::RuntimeOrigin, Self::RoleId, Success = Self::AccountId>;
+/// ```
+pub struct EnsureRoleArg<T>(PhantomData<T>);
+
+impl<T, O> EnsureOriginWithArg<O, T::RoleId> for EnsureRoleArg<T>
+where
+    T: Config,
+    O: Into<Result<Origin<T>, O>>
+        + From<Origin<T>>
+        + Into<Result<RawOrigin<T::AccountId>, O>>
+        + From<RawOrigin<T::AccountId>>,
+{
+    type Success = T::AccountId;
+
+    fn try_origin(o: O, role_id: &T::RoleId) -> Result<Self::Success, O> {
+        try_role_holder::<T, O>(o, *role_id)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin(role_id: &T::RoleId) -> Result<O, ()> {
+        successful_role_holder::<T, O>(*role_id)
+    }
+}
+
+/// Get the account holding the role from a signed or a `RoleHolder` origin
+fn try_role_holder<T, O>(o: O, role_id: T::RoleId) -> Result<T::AccountId, O>
+where
+    T: Config,
+    O: Into<Result<Origin<T>, O>>
+        + From<Origin<T>>
+        + Into<Result<RawOrigin<T::AccountId>, O>>
+        + From<RawOrigin<T::AccountId>>,
+{
+    let o = match <O as Into<Result<Origin<T>, O>>>::into(o) {
+        Ok(Origin::RoleHolder(who, held_role))
+            if held_role == role_id && Pallet::<T>::authorize(&who, &[role_id]) =>
+        {
+            return Ok(who)
+        }
+        Ok(origin) => return Err(O::from(origin)),
+        Err(o) => o,
+    };
+    match <O as Into<Result<RawOrigin<T::AccountId>, O>>>::into(o) {
+        Ok(RawOrigin::Signed(who)) if Pallet::<T>::authorize(&who, &[role_id]) => Ok(who),
+        Ok(origin) => Err(O::from(origin)),
+        Err(o) => Err(o),
+    }
+}
+
+/// Get a signed origin of an account holding the role for the benchmarks,
+/// creating the role first if the benchmark hasn't created it
+#[cfg(feature = "runtime-benchmarks")]
+fn successful_role_holder<T, O>(role_id: T::RoleId) -> Result<O, ()>
+where
+    T: Config,
+    O: From<RawOrigin<T::AccountId>>,
+{
+    use crate::{PreassignRole, Roles};
+
+    if !Roles::<T>::contains_key(role_id) {
+        Pallet::<T>::create_role(role_id, b"benchmark", &[], true, None, true).map_err(|_| ())?;
+    }
+    let who: T::AccountId = frame_benchmarking::account("role_holder", 0, 0);
+    Pallet::<T>::preassign_role(who.clone(), role_id).map_err(|_| ())?;
+    Ok(O::from(RawOrigin::Signed(who)))
+}
@@ -0,0 +1,95 @@
+//! Property tests checking `authorize` against a reference model
+
+use crate::{
+    mock::{new_test_ext, RBACModule, RuntimeOrigin, Test},
+    AddRole, Authorize, Error, PreassignRole,
+};
+use frame_support::{assert_noop, assert_ok};
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+
+/// Account holding the role that grants every other role
+const ADMIN: u64 = 100;
+const USERS: u64 = 4;
+const ROLES: usize = 3;
+
+/// Operation applied to both the pallet and the model
+#[derive(Clone, Debug)]
+enum Op {
+    Grant(u64, usize),
+    Revoke(u64, usize),
+    Suspend(u64),
+    Reinstate(u64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let user = 1..=USERS;
+    let role = 0..ROLES;
+    prop_oneof![
+        (user.clone(), role.clone()).prop_map(|(user, role)| Op::Grant(user, role)),
+        (user.clone(), role).prop_map(|(user, role)| Op::Revoke(user, role)),
+        user.clone().prop_map(Op::Suspend),
+        user.prop_map(Op::Reinstate),
+    ]
+}
+
+proptest! {
+    // A user is authorized by a role only if it was granted and not revoked since,
+    // and the user is not suspended
+    #[test]
+    fn authorize_matches_model(ops in prop::collection::vec(op(), 1..64)) {
+        new_test_ext().execute_with(|| {
+            let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+            RBACModule::preassign_role(ADMIN, role_id_admin).unwrap();
+            let roles: Vec<_> = (0..ROLES)
+                .map(|_| RBACModule::add_role("role".as_bytes(), &[role_id_admin], false).unwrap())
+                .collect();
+
+            let mut assigned = BTreeSet::new();
+            let mut suspended = BTreeSet::new();
+            for op in ops {
+                match op {
+                    Op::Grant(user, role) => {
+                        assert_ok!(RBACModule::grant_role(
+                            RuntimeOrigin::signed(ADMIN),
+                            user,
+                            roles[role]
+                        ));
+                        assigned.insert((user, role));
+                    }
+                    Op::Revoke(user, role) => {
+                        let revoke = || {
+                            RBACModule::revoke_role(RuntimeOrigin::signed(ADMIN), user, roles[role])
+                        };
+                        if assigned.remove(&(user, role)) {
+                            assert_ok!(revoke());
+                        } else {
+                            assert_noop!(revoke(), Error::<Test>::RoleNotGranted);
+                        }
+                    }
+                    Op::Suspend(user) => {
+                        assert_ok!(RBACModule::suspend_account(RuntimeOrigin::root(), user));
+                        suspended.insert(user);
+                    }
+                    Op::Reinstate(user) => {
+                        assert_ok!(RBACModule::reinstate_account(RuntimeOrigin::root(), user));
+                        suspended.remove(&user);
+                    }
+                }
+
+                for user in 1..=USERS {
+                    for (role, role_id) in roles.iter().enumerate() {
+                        let expected = assigned.contains(&(user, role)) && !suspended.contains(&user);
+                        assert_eq!(
+                            RBACModule::authorize(&user, &[*role_id]),
+                            expected,
+                            "user {} role {}",
+                            user,
+                            role_id
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
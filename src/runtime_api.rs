@@ -0,0 +1,73 @@
+//! Runtime API of the pallet
+
+use crate::merkle::MembershipProof;
+use codec::Codec;
+use frame_support::sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    /// API to query the role assignments from outside of the runtime
+    ///
+    /// Implement it in the runtime by calling the pallet's functions of the same names:
+    ///
+    /// ```ignore
+    /// impl pallet_rbac::runtime_api::RbacApi<Block, AccountId, RoleId, Hash, BlockNumber> for Runtime {
+    ///     fn all_assignments() -> Vec<(AccountId, RoleId)> {
+    ///         RBAC::all_assignments()
+    ///     }
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// All lists except the pages of `roles_of_paged` are sorted, so the results are deterministic
+    /// across runtimes. The sorting cost is proportional to the result size.
+    pub trait RbacApi<AccountId, RoleId, Hash, BlockNumber>
+    where
+        AccountId: Codec,
+        RoleId: Codec,
+        Hash: Codec,
+        BlockNumber: Codec,
+    {
+        /// Get all active assignments, for chain state exports and audits.
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain.
+        fn all_assignments() -> Vec<(AccountId, RoleId)>;
+
+        /// Get the active roles of the user
+        fn roles_of(user: AccountId) -> Vec<RoleId>;
+
+        /// Get at most `limit` active roles of the user following `start_after`.
+        /// The pages are in the storage order, not sorted by id. `start_after` is an opaque cursor:
+        /// pass the last role of the previous page to get the next one.
+        fn roles_of_paged(user: AccountId, start_after: Option<RoleId>, limit: u32) -> Vec<RoleId>;
+
+        /// Get the holders of the active role.
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain.
+        fn role_holders(role_id: RoleId) -> Vec<AccountId>;
+
+        /// Authorize the user against the roles whose names start with the prefix.
+        /// It iterates the whole `RoleNameIndex` storage, so it should be called only off-chain.
+        fn authorize_name_prefix(user: AccountId, prefix: Vec<u8>) -> bool;
+
+        /// Check which of the roles exist in a single call, in the order of `role_ids`
+        fn roles_exist(role_ids: Vec<RoleId>) -> Vec<bool>;
+
+        /// Check which of the roles the granter can grant in a single call, in the order of `role_ids`
+        fn grantable_by(granter: AccountId, role_ids: Vec<RoleId>) -> Vec<bool>;
+
+        /// Authorize each of the users against the role in a single call, in the order of `users`
+        fn authorize_many(users: Vec<AccountId>, role_id: RoleId) -> Vec<bool>;
+
+        /// Get the block at which the role was granted to the user, `None` if the user doesn't hold it
+        fn granted_at(user: AccountId, role_id: RoleId) -> Option<BlockNumber>;
+
+        /// Get all metadata entries of the role as key-value pairs
+        fn role_metadata(role_id: RoleId) -> Vec<(Vec<u8>, Vec<u8>)>;
+
+        /// Get the content hash of the role, `None` if there is no such role.
+        /// Clients caching the role info can skip re-fetching it while the hash is unchanged.
+        fn role_hash(role_id: RoleId) -> Option<Hash>;
+
+        /// Get the proof of the assignment against `MembershipRoot`, `None` if the role is not assigned to the user.
+        /// It iterates the whole `Assignments` storage, so it should be called only off-chain.
+        fn membership_proof(user: AccountId, role_id: RoleId) -> Option<MembershipProof<Hash>>;
+    }
+}
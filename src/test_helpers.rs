@@ -0,0 +1,122 @@
+//! Helpers for the consumers that test their pallets against RBAC.
+//! Available only with `test-helpers` feature, so they don't get into production builds.
+
+use crate::{AddRole, Config, Pallet, PreassignRole, RoleNameIndex};
+use frame_support::BoundedVec;
+use sp_runtime::BuildStorage;
+
+/// Role that will be created by the builder
+struct TestRole<T: Config> {
+    name: Vec<u8>,
+    granters: Vec<T::RoleId>,
+    holders: Vec<T::AccountId>,
+}
+
+/// Builder of the externalities with roles already created and granted
+///
+/// Roles are created in the order they were added, so their ids are the same on every build.
+///
+/// ```ignore
+/// let mut ext = RbacTestBuilder::<Test>::new()
+///     .with_role("admin", &[])
+///     .granted_to(1)
+///     .build();
+/// ```
+pub struct RbacTestBuilder<T: Config> {
+    roles: Vec<TestRole<T>>,
+}
+
+impl<T: Config> Default for RbacTestBuilder<T> {
+    fn default() -> Self {
+        Self { roles: Vec::new() }
+    }
+}
+
+impl<T: Config> RbacTestBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a role with the given granters
+    pub fn with_role(mut self, name: &str, granters: &[T::RoleId]) -> Self {
+        self.roles.push(TestRole {
+            name: name.as_bytes().to_vec(),
+            granters: granters.to_vec(),
+            holders: Vec::new(),
+        });
+        self
+    }
+
+    /// Grant the last added role to the account
+    ///
+    /// Panics if no role was added yet.
+    pub fn granted_to(mut self, account: T::AccountId) -> Self {
+        self.roles
+            .last_mut()
+            .expect("`with_role` should be called before `granted_to`")
+            .holders
+            .push(account);
+        self
+    }
+
+    /// Build the externalities with all the roles created and granted
+    pub fn build(self) -> sp_io::TestExternalities {
+        self.build_with_ids().0
+    }
+
+    /// Build the externalities and return the ids of created roles in the order they were added
+    pub fn build_with_ids(self) -> (sp_io::TestExternalities, Vec<T::RoleId>) {
+        let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::<T>::default()
+            .build_storage()
+            .expect("system genesis should be built")
+            .into();
+        let ids = ext.execute_with(|| {
+            self.roles
+                .into_iter()
+                .map(|role| {
+                    let id = Pallet::<T>::add_role(&role.name, &role.granters, false)
+                        .expect("test role should be valid");
+                    for holder in role.holders {
+                        Pallet::<T>::preassign_role(holder, id).expect("test role should exist");
+                    }
+                    id
+                })
+                .collect()
+        });
+        (ext, ids)
+    }
+}
+
+/// Test extension granting roles by their names in one line
+///
+/// ```ignore
+/// new_test_ext().execute_with(|| {
+///     let role_id = RBACModule::quick_grant(1, "admin");
+///     assert!(RBACModule::authorize(&1, &[role_id]));
+/// });
+/// ```
+pub trait QuickGrant<AId, RId> {
+    /// Grant the role with this name to the account, creating a self-assignable role if there is none.
+    /// Should be called inside the externalities.
+    ///
+    /// **Returns**: id of the granted role
+    fn quick_grant(account: AId, role_name: &str) -> RId;
+}
+
+impl<T: Config> QuickGrant<T::AccountId, T::RoleId> for Pallet<T> {
+    fn quick_grant(account: T::AccountId, role_name: &str) -> T::RoleId {
+        let name: BoundedVec<u8, T::NameMaxLength> = role_name
+            .as_bytes()
+            .to_vec()
+            .try_into()
+            .expect("test role name should fit `NameMaxLength`");
+        let role_id = RoleNameIndex::<T>::iter_key_prefix(name)
+            .next()
+            .unwrap_or_else(|| {
+                Pallet::<T>::add_role(role_name.as_bytes(), &[], true)
+                    .expect("test role should be valid")
+            });
+        Pallet::<T>::preassign_role(account, role_id).expect("test role should exist");
+        role_id
+    }
+}
@@ -1,11 +1,23 @@
 use crate::{
     mock::{
-        new_test_ext, GrantersListMaxLength, NameMaxLength, RBACModule, RuntimeOrigin, System, Test,
+        new_test_ext, reentrant_revocations, removed_roles, AuditLogSize, BatchMaxLength, Consumer,
+        ConsumerAdminRole, EmptyRolesAllow, FlapCooldown, GrantersListMaxLength, MaxConflicts,
+        MaxDependencies, MaxFailedAttempts, MaxMembershipSize, MaxMetadataPerRole, MaxPurge,
+        MaxRolesPerAccount, NameMaxLength, RBACModule, RecycleRoleIds, ReenterOnRoleRemoved,
+        RejectDefaultAccount, RemarkRole, ResetWindow, RoleIdStart, RuntimeCall, RuntimeOrigin,
+        System, Test, TestCallRoles, TrackAuthorizeMetrics, TrackMembershipRoot,
     },
-    AddRole, Authorize, Error, Event, InterfaceError, PreassignRole,
+    AddOwnedRole, AddRole, Authorize, AuthorizeByName, AuthorizePermission, CheckGrantAttempts,
+    CheckRole, Error, Event, GranterCount, InterfaceError, PreassignRole, ReserveIdRange,
+    RoleProvider, RoleTemplate,
+};
+use codec::Decode;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use sp_core::{ConstU32, Get};
+use sp_runtime::{
+    traits::{Dispatchable, SignedExtension},
+    DispatchResult,
 };
-use frame_support::{assert_noop, assert_ok};
-use sp_core::Get;
 
 /// Add two roles and check that their ids are different
 #[test]
@@ -51,11 +63,30 @@ fn test_grant_revoke_and_authorize() {
             Event::RoleGranted {
                 user: account_id_user,
                 role_id: role_id_user,
+                newly_granted: true,
+                total_holders: 1,
             }
             .into(),
         );
         assert!(RBACModule::authorize(&account_id_user, &[role_id_user]));
 
+        // Grant the role once again, nothing changes
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(account_id_admin),
+            account_id_user,
+            role_id_user,
+        ));
+        System::assert_last_event(
+            Event::RoleGranted {
+                user: account_id_user,
+                role_id: role_id_user,
+                newly_granted: false,
+                total_holders: 1,
+            }
+            .into(),
+        );
+        assert_eq!(RBACModule::holder_count(role_id_user), 1);
+
         // Revoke the role
         assert_ok!(RBACModule::revoke_role(
             RuntimeOrigin::signed(account_id_admin),
@@ -68,6 +99,7 @@ fn test_grant_revoke_and_authorize() {
             Event::RoleRevoked {
                 user: account_id_user,
                 role_id: role_id_user,
+                total_holders: 0,
             }
             .into(),
         );
@@ -97,7 +129,7 @@ fn test_grant_not_authorized() {
                 account_id_user,
                 role_id_admin,
             ),
-            Error::<Test>::NotAuthorized
+            Error::<Test>::NotAuthorizedToGrant
         );
 
         // Check that user who was tried to grant a role can't authorize
@@ -159,3 +191,4449 @@ fn test_too_many_granters() {
         );
     });
 }
+
+// Limit the role to a window of blocks and check authorization before, during and after it
+#[test]
+fn test_role_window() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_voter =
+            RBACModule::add_role("voter".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_voter = 2_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        RBACModule::preassign_role(account_id_voter, role_id_voter).unwrap();
+
+        // Set the block number so event get written to the chain
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(account_id_admin),
+            role_id_voter,
+            Some(10),
+            Some(20),
+        ));
+        System::assert_last_event(
+            Event::RoleWindowSet {
+                role_id: role_id_voter,
+                active_from: Some(10),
+                active_to: Some(20),
+            }
+            .into(),
+        );
+
+        // Before the window
+        System::set_block_number(9);
+        assert!(!RBACModule::authorize(&account_id_voter, &[role_id_voter]));
+
+        // During the window
+        System::set_block_number(10);
+        assert!(RBACModule::authorize(&account_id_voter, &[role_id_voter]));
+        System::set_block_number(20);
+        assert!(RBACModule::authorize(&account_id_voter, &[role_id_voter]));
+
+        // After the window
+        System::set_block_number(21);
+        assert!(!RBACModule::authorize(&account_id_voter, &[role_id_voter]));
+    });
+}
+
+// Set the window from the user who is not a granter and with the bounds in the wrong order
+#[test]
+fn test_role_window_errors() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_not_authorized = 2_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::set_role_window(
+                RuntimeOrigin::signed(account_id_not_authorized),
+                role_id_admin,
+                Some(10),
+                Some(20),
+            ),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_noop!(
+            RBACModule::set_role_window(
+                RuntimeOrigin::signed(account_id_admin),
+                role_id_admin,
+                Some(20),
+                Some(10),
+            ),
+            Error::<Test>::InvalidWindow
+        );
+    });
+}
+
+// Check both outcomes of the fallible authorization
+#[test]
+fn test_try_authorize() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_user = 2_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        assert_eq!(
+            RBACModule::try_authorize(&account_id_admin, &[role_id_admin]),
+            Ok(())
+        );
+        assert_eq!(
+            RBACModule::try_authorize(&account_id_user, &[role_id_admin]),
+            Err(InterfaceError::NotAuthorized)
+        );
+    });
+}
+
+/// Run `f` against the committed state, returning its result and the number of the trie nodes it has read.
+/// Each storage key read adds its nodes, so fewer nodes mean fewer reads.
+fn with_read_nodes<R>(ext: &mut sp_io::TestExternalities, f: impl FnOnce() -> R) -> (R, usize) {
+    ext.commit_all().unwrap();
+    let (result, proof) = ext.execute_and_prove(f);
+    (result, proof.into_nodes().into_iter().count())
+}
+
+// Check that the priority hint makes authorization stop at the first check when it hits
+#[test]
+fn test_authorize_ordered() {
+    let account_id_writer = 1_u64;
+    let account_id_user = 2_u64;
+
+    let mut ext = new_test_ext();
+    let roles = ext.execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_moderator =
+            RBACModule::add_role("moderator".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_writer =
+            RBACModule::add_role("writer".as_bytes(), &[role_id_admin], false).unwrap();
+
+        // The admin and moderator assignments are not active yet, so checking them costs reads of their windows
+        for role_id in [role_id_admin, role_id_moderator, role_id_writer] {
+            RBACModule::preassign_role(account_id_writer, role_id).unwrap();
+        }
+        crate::AssignmentActiveFrom::<Test>::insert(account_id_writer, role_id_admin, 100);
+        crate::AssignmentActiveFrom::<Test>::insert(account_id_writer, role_id_moderator, 100);
+
+        // Priority roles outside of the list are ignored
+        assert!(!RBACModule::authorize_ordered(
+            &account_id_writer,
+            &[role_id_admin],
+            &[role_id_writer]
+        ));
+
+        [role_id_admin, role_id_moderator, role_id_writer]
+    });
+    let role_id_writer = roles[2];
+
+    // Plain authorization goes through the whole list, hinted authorization stops at the priority role
+    let (authorized, plain) = with_read_nodes(&mut ext, || {
+        RBACModule::authorize(&account_id_writer, &roles)
+    });
+    assert!(authorized);
+    let (authorized, hinted) = with_read_nodes(&mut ext, || {
+        RBACModule::authorize_ordered(&account_id_writer, &roles, &[role_id_writer])
+    });
+    assert!(authorized);
+    assert!(hinted < plain);
+
+    // Every role is checked only once on a miss, as the plain authorization does
+    let (authorized, plain) =
+        with_read_nodes(&mut ext, || RBACModule::authorize(&account_id_user, &roles));
+    assert!(!authorized);
+    let (authorized, hinted) = with_read_nodes(&mut ext, || {
+        RBACModule::authorize_ordered(&account_id_user, &roles, &[role_id_writer])
+    });
+    assert!(!authorized);
+    assert_eq!(hinted, plain);
+}
+
+// Build the externalities with the test helper and check that roles are set up
+#[cfg(feature = "test-helpers")]
+#[test]
+fn test_rbac_test_builder() {
+    let (mut ext, ids) = crate::test_helpers::RbacTestBuilder::<Test>::new()
+        .with_role("admin", &[])
+        .granted_to(1)
+        .granted_to(2)
+        .with_role("user", &[1])
+        .granted_to(3)
+        .build_with_ids();
+
+    ext.execute_with(|| {
+        let [role_id_admin, role_id_user] = ids[..] else {
+            panic!("two roles should be created")
+        };
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+        assert!(RBACModule::authorize(&2, &[role_id_admin]));
+        assert!(!RBACModule::authorize(&3, &[role_id_admin]));
+        assert!(RBACModule::authorize(&3, &[role_id_user]));
+        assert_eq!(
+            RBACModule::roles(role_id_user)
+                .unwrap()
+                .granters
+                .into_inner(),
+            vec![role_id_admin]
+        );
+    });
+}
+
+// The account that granted the role can revoke it after it has lost the granter role
+#[test]
+fn test_revoke_by_original_granter() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_former_admin = 2_u64;
+        let account_id_user = 3_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        RBACModule::preassign_role(account_id_former_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(account_id_former_admin),
+            account_id_user,
+            role_id_user,
+        ));
+        assert_eq!(
+            RBACModule::assignment_granter(account_id_user, role_id_user),
+            Some(account_id_former_admin)
+        );
+
+        // The granter is not a part of granters list anymore
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(account_id_admin),
+            account_id_former_admin,
+            role_id_admin,
+        ));
+        assert!(!RBACModule::authorize(
+            &account_id_former_admin,
+            &[role_id_admin]
+        ));
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(account_id_former_admin),
+            account_id_user,
+            role_id_user,
+        ));
+        assert!(!RBACModule::authorize(&account_id_user, &[role_id_user]));
+        assert_eq!(
+            RBACModule::assignment_granter(account_id_user, role_id_user),
+            None
+        );
+
+        // Once the assignment is gone, the former granter has no rights over the role
+        assert_noop!(
+            RBACModule::revoke_role(
+                RuntimeOrigin::signed(account_id_former_admin),
+                account_id_user,
+                role_id_user,
+            ),
+            Error::<Test>::NotAuthorizedToRevoke
+        );
+    });
+}
+
+// Check that the limits are exposed in the metadata
+#[test]
+fn test_constants_metadata() {
+    let constants = RBACModule::pallet_constants_metadata();
+    let value_of = |name: &str| {
+        let constant = constants
+            .iter()
+            .find(|constant| constant.name == name)
+            .expect("constant should be in metadata");
+        u32::decode(&mut &constant.value[..]).unwrap()
+    };
+
+    assert_eq!(value_of("NameMaxLength"), NameMaxLength::get());
+    assert_eq!(
+        value_of("GrantersListMaxLength"),
+        GrantersListMaxLength::get()
+    );
+}
+
+// Grant a role to a batch where some users already hold it and check the summary
+#[test]
+fn test_grant_role_batch() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+        RBACModule::preassign_role(3, role_id_user).unwrap();
+
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::grant_role_batch(
+            RuntimeOrigin::signed(account_id_admin),
+            vec![2, 3, 4, 5, 6].try_into().unwrap(),
+            role_id_user,
+        ));
+
+        System::assert_has_event(
+            Event::RoleGranted {
+                user: 4,
+                role_id: role_id_user,
+                newly_granted: true,
+                total_holders: 3,
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::BatchGrantCompleted {
+                role_id: role_id_user,
+                granted: 3,
+                skipped: 2,
+            }
+            .into(),
+        );
+        for user in 2..=6 {
+            assert!(RBACModule::authorize(&user, &[role_id_user]));
+        }
+        // Preassigned roles keep having no granter
+        assert_eq!(RBACModule::assignment_granter(2, role_id_user), None);
+        assert_eq!(
+            RBACModule::assignment_granter(4, role_id_user),
+            Some(account_id_admin)
+        );
+
+        assert_noop!(
+            RBACModule::grant_role_batch(
+                RuntimeOrigin::signed(2),
+                vec![7].try_into().unwrap(),
+                role_id_user,
+            ),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+    });
+}
+
+// Check which of the candidate roles the user holds
+#[test]
+fn test_roles_held_among() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_moderator =
+            RBACModule::add_role("moderator".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_writer =
+            RBACModule::add_role("writer".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_user = 1_u64;
+
+        RBACModule::preassign_role(account_id_user, role_id_moderator).unwrap();
+        RBACModule::preassign_role(account_id_user, role_id_writer).unwrap();
+
+        // Partial overlap
+        assert_eq!(
+            RBACModule::roles_held_among(
+                &account_id_user,
+                &[role_id_writer, role_id_admin, role_id_moderator]
+            ),
+            vec![role_id_writer, role_id_moderator]
+        );
+
+        // No overlap
+        assert!(RBACModule::roles_held_among(&account_id_user, &[role_id_admin]).is_empty());
+        assert!(RBACModule::roles_held_among(&2, &[role_id_writer]).is_empty());
+    });
+}
+
+// Revoke a protected role down to its last holder and check that the last one stays
+#[test]
+fn test_protected_role() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_admin).unwrap();
+        RBACModule::preassign_role(3, role_id_admin).unwrap();
+        assert_eq!(RBACModule::holder_count(role_id_admin), 3);
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::protect_role(RuntimeOrigin::signed(1), role_id_admin),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            RBACModule::protect_role(RuntimeOrigin::root(), role_id_admin + 1),
+            Error::<Test>::RoleNotExist
+        );
+        assert_ok!(RBACModule::protect_role(
+            RuntimeOrigin::root(),
+            role_id_admin
+        ));
+        System::assert_last_event(
+            Event::RoleProtected {
+                role_id: role_id_admin,
+            }
+            .into(),
+        );
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_admin
+        ));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+        assert_eq!(RBACModule::holder_count(role_id_admin), 1);
+
+        // The last holder can't revoke the role from itself
+        assert_noop!(
+            RBACModule::revoke_role(RuntimeOrigin::signed(1), 1, role_id_admin),
+            Error::<Test>::CannotRemoveLastHolderOfProtectedRole
+        );
+
+        // Once the protection is removed it can
+        assert_ok!(RBACModule::unprotect_role(
+            RuntimeOrigin::root(),
+            role_id_admin
+        ));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            1,
+            role_id_admin
+        ));
+        assert_eq!(RBACModule::holder_count(role_id_admin), 0);
+    });
+}
+
+// Generate ids from the counter for the role id without `Incrementable`
+#[test]
+fn test_sequential_ids() {
+    use crate::mock::sequential::{self, RoleId};
+
+    sequential::new_test_ext().execute_with(|| {
+        let role_id_1 = sequential::RBACModule::add_role("role_1".as_bytes(), &[], true).unwrap();
+        let role_id_2 =
+            sequential::RBACModule::add_role("role_2".as_bytes(), &[role_id_1], false).unwrap();
+
+        assert_eq!(role_id_1, RoleId(1));
+        assert_eq!(role_id_2, RoleId(2));
+
+        // Use up the rest of the ids
+        for _ in 3..=u8::MAX {
+            sequential::RBACModule::add_role("role".as_bytes(), &[], false).unwrap();
+        }
+        assert_eq!(
+            sequential::RBACModule::add_role("role".as_bytes(), &[], false),
+            Err(InterfaceError::IdsExhausted)
+        );
+    });
+}
+
+// Use up the ids of a small `Incrementable` role id and check that adding roles fails instead of looping
+#[test]
+fn test_incrementable_ids_exhausted() {
+    use crate::mock::small;
+
+    small::new_test_ext().execute_with(|| {
+        for expected in 1..=u8::MAX {
+            assert_eq!(
+                small::RBACModule::add_role("role".as_bytes(), &[], false),
+                Ok(expected)
+            );
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                small::RBACModule::add_role("role".as_bytes(), &[], false),
+                Err(InterfaceError::IdsExhausted)
+            );
+        }
+        assert!(small::RBACModule::roles(u8::MAX).is_some());
+    });
+}
+
+// Deny a granted role and check that the denial beats the assignment
+#[test]
+fn test_deny_role() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_writer =
+            RBACModule::add_role("writer".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_reader =
+            RBACModule::add_role("reader".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_user = 2_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        RBACModule::preassign_role(account_id_user, role_id_writer).unwrap();
+        RBACModule::preassign_role(account_id_user, role_id_reader).unwrap();
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::deny_role(
+                RuntimeOrigin::signed(account_id_user),
+                account_id_user,
+                role_id_writer,
+            ),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(account_id_admin),
+            account_id_user,
+            role_id_writer,
+        ));
+        System::assert_last_event(
+            Event::RoleDenied {
+                user: account_id_user,
+                role_id: role_id_writer,
+            }
+            .into(),
+        );
+
+        // The role is still assigned but can't be used
+        assert!(RBACModule::assignment_info(account_id_user, role_id_writer).is_some());
+        assert!(!RBACModule::authorize(&account_id_user, &[role_id_writer]));
+        // Denied role fails the whole list even if another role is held
+        assert!(!RBACModule::authorize(
+            &account_id_user,
+            &[role_id_reader, role_id_writer]
+        ));
+        assert!(!RBACModule::authorize_ordered(
+            &account_id_user,
+            &[role_id_reader, role_id_writer],
+            &[role_id_reader]
+        ));
+        assert!(RBACModule::authorize(&account_id_user, &[role_id_reader]));
+
+        assert_ok!(RBACModule::undeny_role(
+            RuntimeOrigin::signed(account_id_admin),
+            account_id_user,
+            role_id_writer,
+        ));
+        System::assert_last_event(
+            Event::RoleUndenied {
+                user: account_id_user,
+                role_id: role_id_writer,
+            }
+            .into(),
+        );
+        assert!(RBACModule::authorize(&account_id_user, &[role_id_writer]));
+    });
+}
+
+// Preassign a role that does not exist and check that the error points to it
+#[test]
+fn test_preassign_role_not_exists() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let non_existent_role = role_id_admin + 1;
+
+        assert_eq!(
+            RBACModule::preassign_role(1, non_existent_role),
+            Err(InterfaceError::RoleNotExist {
+                role: non_existent_role
+            })
+        );
+    });
+}
+
+// Authorize against the bounded role list
+#[test]
+fn test_authorize_bounded() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        RBACModule::preassign_role(1, role_id_user).unwrap();
+
+        let roles: BoundedVec<u32, ConstU32<2>> =
+            vec![role_id_admin, role_id_user].try_into().unwrap();
+        assert!(RBACModule::authorize_bounded(&1, &roles));
+        assert!(!RBACModule::authorize_bounded(&2, &roles));
+    });
+}
+
+/// Dispatch the grant through `CheckGrantAttempts` like the executive does
+fn grant_with_extension(who: u64, user: u64, role_id: u32) -> DispatchResult {
+    dispatch_with_extension(
+        who,
+        RuntimeCall::RBACModule(crate::Call::grant_role { user, role_id }),
+    )
+}
+
+/// Dispatch the call through `CheckGrantAttempts` like the executive does
+fn dispatch_with_extension(who: u64, call: RuntimeCall) -> DispatchResult {
+    let info = Default::default();
+    let pre = CheckGrantAttempts::<Test>::new()
+        .pre_dispatch(&who, &call, &info, 0)
+        .unwrap();
+    let result = call.dispatch(RuntimeOrigin::signed(who));
+    let post_info = result.unwrap_or_else(|err| err.post_info);
+    let result = result.map(|_| ()).map_err(|err| err.error);
+    CheckGrantAttempts::<Test>::post_dispatch(Some(pre), &info, &post_info, 0, &result).unwrap();
+    result
+}
+
+// Fail to grant the role until the origin is locked out and check that it recovers after the window
+#[test]
+fn test_failed_grant_attempts() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+
+        let account_id_admin = 1_u64;
+        let account_id_attacker = 2_u64;
+
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        for _ in 0..MaxFailedAttempts::get() {
+            assert_eq!(
+                grant_with_extension(account_id_attacker, account_id_attacker, role_id_admin),
+                Err(Error::<Test>::NotAuthorizedToGrant.into())
+            );
+        }
+        assert_eq!(
+            RBACModule::failed_grant_attempts(account_id_attacker),
+            Some((1, MaxFailedAttempts::get()))
+        );
+
+        // Even a grant that would succeed is rejected now
+        RBACModule::preassign_role(account_id_attacker, role_id_admin).unwrap();
+        assert_eq!(
+            grant_with_extension(account_id_attacker, 3, role_id_admin),
+            Err(Error::<Test>::TooManyFailedAttempts.into())
+        );
+        // Other accounts are not affected
+        assert_ok!(grant_with_extension(account_id_admin, 3, role_id_admin));
+
+        // The attempts are reset after the window
+        System::set_block_number(1 + ResetWindow::get());
+        assert_ok!(grant_with_extension(account_id_attacker, 4, role_id_admin));
+        assert!(RBACModule::authorize(&4, &[role_id_admin]));
+    });
+}
+
+// Failed grants wrapped in a call returning their error are counted too
+#[test]
+fn test_failed_grant_attempts_wrapped() {
+    use crate::mock::consumer;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        System::set_block_number(1);
+
+        let wrapped = RuntimeCall::Consumer(consumer::Call::wrap {
+            call: Box::new(RuntimeCall::RBACModule(crate::Call::grant_role {
+                user: 2,
+                role_id: role_id_admin,
+            })),
+        });
+        assert_eq!(
+            dispatch_with_extension(2, wrapped),
+            Err(Error::<Test>::NotAuthorizedToGrant.into())
+        );
+        assert_eq!(RBACModule::failed_grant_attempts(2), Some((1, 1)));
+
+        // Other failures are not counted
+        assert!(
+            dispatch_with_extension(2, RuntimeCall::Consumer(consumer::Call::gated {})).is_err()
+        );
+        assert_eq!(RBACModule::failed_grant_attempts(2), Some((1, 1)));
+    });
+}
+
+// Purge a role with more holders than a single call can remove
+#[test]
+fn test_purge_role() {
+    new_test_ext().execute_with(|| {
+        FlapCooldown::set(5);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        // 10 holders and a denial, 12 items with the role itself
+        assert_ok!(RBACModule::grant_role_batch(
+            RuntimeOrigin::signed(account_id_admin),
+            (10..20).collect::<Vec<_>>().try_into().unwrap(),
+            role_id_user,
+        ));
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(account_id_admin),
+            20,
+            role_id_user,
+        ));
+        assert_ok!(RBACModule::protect_role(
+            RuntimeOrigin::root(),
+            role_id_user
+        ));
+
+        assert_noop!(
+            RBACModule::purge_role(RuntimeOrigin::signed(account_id_admin), role_id_user),
+            sp_runtime::DispatchError::BadOrigin
+        );
+
+        // The role with its holders doesn't fit into a single call
+        assert_noop!(
+            RBACModule::purge_role(RuntimeOrigin::root(), role_id_user),
+            Error::<Test>::TooManyToPurge
+        );
+
+        // 3 holders are left with the role, the revocation records and the denial are purged in chunks
+        assert_ok!(RBACModule::revoke_role_batch(
+            RuntimeOrigin::signed(account_id_admin),
+            (10..17).collect::<Vec<_>>().try_into().unwrap(),
+            role_id_user,
+        ));
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        System::assert_last_event(
+            Event::RolePurged {
+                role_id: role_id_user,
+                removed: MaxPurge::get(),
+                complete: false,
+            }
+            .into(),
+        );
+        // The role can't be granted anymore
+        assert!(RBACModule::roles(role_id_user).is_none());
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(account_id_admin), 30, role_id_user),
+            Error::<Test>::RoleNotExist
+        );
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        System::assert_last_event(
+            Event::RolePurged {
+                role_id: role_id_user,
+                removed: MaxPurge::get(),
+                complete: false,
+            }
+            .into(),
+        );
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        System::assert_last_event(
+            Event::RolePurged {
+                role_id: role_id_user,
+                removed: 2,
+                complete: true,
+            }
+            .into(),
+        );
+
+        for user in 10..=20 {
+            assert!(RBACModule::assignment_info(user, role_id_user).is_none());
+            assert!(!RBACModule::denials(user, role_id_user));
+            assert_eq!(RBACModule::last_revoked(user, role_id_user), None);
+            assert_eq!(RBACModule::assignment_granter(user, role_id_user), None);
+        }
+        assert_eq!(RBACModule::holder_count(role_id_user), 0);
+        assert!(!RBACModule::protected_roles(role_id_user));
+        // Other roles are untouched
+        assert!(RBACModule::authorize(&account_id_admin, &[role_id_admin]));
+
+        assert_noop!(
+            RBACModule::purge_role(RuntimeOrigin::root(), role_id_user),
+            Error::<Test>::RoleNotExist
+        );
+    });
+}
+
+// Authorize against hierarchical role names by their prefix
+#[test]
+fn test_authorize_name_prefix() {
+    new_test_ext().execute_with(|| {
+        let role_id_finance_read =
+            RBACModule::add_role("team.finance.read".as_bytes(), &[], true).unwrap();
+        RBACModule::add_role("team.finance.write".as_bytes(), &[], true).unwrap();
+        let role_id_hr_read = RBACModule::add_role("team.hr.read".as_bytes(), &[], true).unwrap();
+
+        let account_id_finance = 1_u64;
+        let account_id_hr = 2_u64;
+
+        RBACModule::preassign_role(account_id_finance, role_id_finance_read).unwrap();
+        RBACModule::preassign_role(account_id_hr, role_id_hr_read).unwrap();
+
+        assert!(RBACModule::authorize_name_prefix(
+            &account_id_finance,
+            b"team.finance."
+        ));
+        assert!(RBACModule::authorize_name_prefix(
+            &account_id_finance,
+            b"team."
+        ));
+        assert!(RBACModule::authorize_name_prefix(
+            &account_id_finance,
+            b"team.finance.read"
+        ));
+        assert!(!RBACModule::authorize_name_prefix(
+            &account_id_finance,
+            b"team.hr"
+        ));
+        assert!(!RBACModule::authorize_name_prefix(
+            &account_id_finance,
+            b"team.finance.write"
+        ));
+
+        assert!(RBACModule::authorize_name_prefix(
+            &account_id_hr,
+            b"team.hr."
+        ));
+        assert!(!RBACModule::authorize_name_prefix(
+            &account_id_hr,
+            b"team.finance"
+        ));
+    });
+}
+
+// Every call should have its own non-zero weight in `SubstrateWeight`.
+// A new call without an entry here fails the test, so its weight won't be forgotten.
+#[test]
+fn test_weights_cover_all_calls() {
+    use crate::{weights::SubstrateWeight, WeightInfo};
+    use frame_support::{dispatch::GetCallName, weights::Weight};
+
+    type Weights = SubstrateWeight<Test>;
+    let batch = BatchMaxLength::get();
+
+    for name in <crate::Call<Test> as GetCallName>::get_call_names() {
+        let weight = match *name {
+            "grant_role" => Weights::grant_role(GrantersListMaxLength::get()),
+            "revoke_role" => Weights::revoke_role(GrantersListMaxLength::get())
+                .saturating_add(Weights::revoke_dependents(MaxDependencies::get())),
+            "set_role_window" => Weights::set_role_window(),
+            "grant_role_batch" => {
+                Weights::grant_role_batch(batch, GrantersListMaxLength::get(), MaxConflicts::get())
+            }
+            "protect_role" => Weights::protect_role(),
+            "unprotect_role" => Weights::unprotect_role(),
+            "deny_role" => Weights::deny_role(),
+            "undeny_role" => Weights::undeny_role(),
+            "purge_role" => Weights::purge_role(
+                MaxPurge::get(),
+                MaxConflicts::get(),
+                MaxDependencies::get() * 2,
+            ),
+            "suspend_account" => Weights::suspend_account(),
+            "reinstate_account" => Weights::reinstate_account(),
+            "set_conflicts" => Weights::set_conflicts(MaxConflicts::get()),
+            "revoke_role_batch" => Weights::revoke_role_batch(batch, GrantersListMaxLength::get()),
+            "set_self_assignable" => Weights::set_self_assignable(GrantersListMaxLength::get()),
+            "transfer_role_ownership" => Weights::transfer_role_ownership(),
+            "grant_role_from" => Weights::grant_role_from(GrantersListMaxLength::get()),
+            "grant_role_windowed" => Weights::grant_role_windowed(GrantersListMaxLength::get()),
+            "force_preassign" => Weights::force_preassign(),
+            "add_permission" => Weights::add_permission(),
+            "remove_permission" => Weights::remove_permission(),
+            "remove_granter" => Weights::remove_granter(GrantersListMaxLength::get()),
+            "set_role_metadata" => Weights::set_role_metadata(),
+            "sponsor_and_grant" => Weights::sponsor_and_grant(GrantersListMaxLength::get()),
+            "create_roles_from_template" => Weights::create_roles_from_template(),
+            "set_dependencies" => Weights::set_dependencies(MaxDependencies::get()),
+            "set_role_uri" => Weights::set_role_uri(GrantersListMaxLength::get()),
+            "grant_roles" => Weights::grant_roles(batch, GrantersListMaxLength::get()),
+            other => panic!("call `{other}` has no weight in `WeightInfo`"),
+        };
+        assert!(weight != Weight::zero(), "call `{name}` has zero weight");
+    }
+}
+
+// Successive grants report the growing number of holders and a revoke decrements it
+#[test]
+fn test_total_holders_in_events() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        for (user, total_holders) in [(2_u64, 1_u32), (3, 2), (4, 3)] {
+            assert_ok!(RBACModule::grant_role(
+                RuntimeOrigin::signed(account_id_admin),
+                user,
+                role_id_user,
+            ));
+            System::assert_last_event(
+                Event::RoleGranted {
+                    user,
+                    role_id: role_id_user,
+                    newly_granted: true,
+                    total_holders,
+                }
+                .into(),
+            );
+        }
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(account_id_admin),
+            3,
+            role_id_user,
+        ));
+        System::assert_last_event(
+            Event::RoleRevoked {
+                user: 3,
+                role_id: role_id_user,
+                total_holders: 2,
+            }
+            .into(),
+        );
+    });
+}
+
+// Grant roles by their names with the test extension
+#[cfg(feature = "test-helpers")]
+#[test]
+fn test_quick_grant() {
+    use crate::test_helpers::QuickGrant;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::quick_grant(1, "admin");
+        // The same role is found by its name
+        assert_eq!(RBACModule::quick_grant(2, "admin"), role_id_admin);
+        let role_id_user = RBACModule::quick_grant(3, "user");
+        assert!(role_id_admin != role_id_user);
+
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+        assert!(RBACModule::authorize(&2, &[role_id_admin]));
+        assert!(!RBACModule::authorize(&3, &[role_id_admin]));
+        assert!(RBACModule::authorize(&3, &[role_id_user]));
+    });
+}
+
+/// Build the storage with the pallet's genesis config
+fn genesis_ext(config: crate::GenesisConfig<Test>) -> sp_io::TestExternalities {
+    use sp_runtime::BuildStorage;
+
+    let mut storage = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+    config.assimilate_storage(&mut storage).unwrap();
+    storage.into()
+}
+
+// Create roles in genesis where every role can be granted
+#[test]
+fn test_genesis_roles() {
+    // admin grants itself and has a holder, moderator is granted by admin
+    genesis_ext(crate::GenesisConfig {
+        roles: vec![
+            (b"admin".to_vec(), vec![], true),
+            (b"moderator".to_vec(), vec![1], false),
+        ],
+        assignments: vec![(1, 1)],
+        granters: vec![],
+    })
+    .execute_with(|| {
+        assert!(RBACModule::authorize(&1, &[1]));
+        assert_eq!(RBACModule::roles(2).unwrap().granters.into_inner(), vec![1]);
+    });
+}
+
+// An admin role without granters and holders locks the administration
+#[test]
+#[should_panic(expected = "nobody will be able to grant it")]
+fn test_genesis_role_not_grantable() {
+    genesis_ext(crate::GenesisConfig {
+        roles: vec![
+            (b"admin".to_vec(), vec![], false),
+            (b"moderator".to_vec(), vec![1], false),
+        ],
+        assignments: vec![],
+        granters: vec![],
+    });
+}
+
+// Roles granted through a chain of granters need only a holder at the top of the chain
+#[test]
+fn test_genesis_roles_granted_through_chain() {
+    // viewer is granted by editor, editor is granted by admin, only admin has a holder
+    genesis_ext(crate::GenesisConfig {
+        roles: vec![
+            (b"admin".to_vec(), vec![], false),
+            (b"editor".to_vec(), vec![1], false),
+            (b"viewer".to_vec(), vec![2], false),
+        ],
+        assignments: vec![(1, 1)],
+        granters: vec![],
+    })
+    .execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, 2));
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(2), 3, 3));
+        assert!(RBACModule::authorize(&3, &[3]));
+    });
+}
+
+// Grant a role with several granters and check that only the actual granters are charged
+#[test]
+fn test_grant_role_weight() {
+    use crate::WeightInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_moderator =
+            RBACModule::add_role("moderator".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_user = RBACModule::add_role(
+            "user".as_bytes(),
+            &[role_id_admin, role_id_moderator],
+            false,
+        )
+        .unwrap();
+
+        RBACModule::preassign_role(1, role_id_moderator).unwrap();
+
+        let post_info = RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id_user).unwrap();
+        assert_eq!(post_info.actual_weight, Some(<()>::grant_role(2)));
+        assert!(
+            <()>::grant_role(2).ref_time()
+                < <()>::grant_role(GrantersListMaxLength::get()).ref_time()
+        );
+    });
+}
+
+type TestRoleInfo = crate::RoleInfo<u32, u64, ConstU32<5>, ConstU32<3>, ConstU32<2>, u64>;
+
+// Build the role info checking its invariants without the pallet storage
+#[test]
+fn test_role_info_try_new() {
+    let exists = |id: &u32| *id < 10;
+
+    // Self id is added and duplicates are removed
+    let role = TestRoleInfo::try_new(10, b"admin", &[1, 2, 1], true, exists).unwrap();
+    assert_eq!(role.name.into_inner(), b"admin".to_vec());
+    assert_eq!(role.granters.into_inner(), vec![1, 2, 10]);
+    assert_eq!(role.active_from, None);
+    assert_eq!(role.active_to, None);
+    assert!(role.conflicts.is_empty());
+    assert_eq!(role.modified_at, 0);
+    assert_eq!(role.owner, None);
+
+    assert_eq!(
+        TestRoleInfo::try_new(10, b"administrator", &[], true, exists),
+        Err(InterfaceError::NameTooLong {
+            expected: 5,
+            observed: 13
+        })
+    );
+    assert_eq!(
+        TestRoleInfo::try_new(10, b"admin", &[1, 2, 3], true, exists),
+        Err(InterfaceError::GrantersListTooLong {
+            expected: 3,
+            observed: 4
+        })
+    );
+    assert_eq!(
+        TestRoleInfo::try_new(10, b"admin", &[1, 11], false, exists),
+        Err(InterfaceError::RoleNotExist { role: 11 })
+    );
+}
+
+// Add a role granted by a role that doesn't exist
+#[test]
+fn test_add_role_granter_not_exists() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        assert_eq!(
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin, 42], false),
+            Err(InterfaceError::RoleNotExist { role: 42 })
+        );
+    });
+}
+
+// Suspend the user and check that all of its roles fail until it is reinstated
+#[test]
+fn test_suspend_account() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_user = 1_u64;
+
+        RBACModule::preassign_role(account_id_user, role_id_admin).unwrap();
+        RBACModule::preassign_role(account_id_user, role_id_user).unwrap();
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::suspend_account(RuntimeOrigin::signed(account_id_user), account_id_user),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(RBACModule::suspend_account(
+            RuntimeOrigin::root(),
+            account_id_user
+        ));
+        System::assert_last_event(
+            Event::AccountSuspended {
+                user: account_id_user,
+            }
+            .into(),
+        );
+
+        assert!(!RBACModule::authorize(&account_id_user, &[role_id_admin]));
+        assert!(!RBACModule::authorize(&account_id_user, &[role_id_user]));
+        assert!(!RBACModule::authorize_ordered(
+            &account_id_user,
+            &[role_id_user],
+            &[role_id_user]
+        ));
+        // Suspended account can't grant roles either
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(account_id_user), 2, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        // Assignments are kept
+        assert!(RBACModule::assignment_info(account_id_user, role_id_user).is_some());
+
+        assert_ok!(RBACModule::reinstate_account(
+            RuntimeOrigin::root(),
+            account_id_user
+        ));
+        System::assert_last_event(
+            Event::AccountReinstated {
+                user: account_id_user,
+            }
+            .into(),
+        );
+        assert!(RBACModule::authorize(&account_id_user, &[role_id_admin]));
+        assert!(RBACModule::authorize(&account_id_user, &[role_id_user]));
+    });
+}
+
+// Allocate roles within reserved ranges until one of them is exhausted
+#[test]
+fn test_id_ranges() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(RBACModule::reserve_id_range(1000, 1002));
+        assert_ok!(RBACModule::reserve_id_range(2000, 2999));
+        // Ranges can't overlap or be reversed
+        assert_eq!(
+            RBACModule::reserve_id_range(1002, 1500),
+            Err(InterfaceError::InvalidRange)
+        );
+        assert_eq!(
+            RBACModule::reserve_id_range(1600, 1500),
+            Err(InterfaceError::InvalidRange)
+        );
+        // Range should fit `RoleId`
+        assert_eq!(
+            RBACModule::reserve_id_range(u32::MAX as u128, u32::MAX as u128 + 1),
+            Err(InterfaceError::InvalidRange)
+        );
+
+        let role_id_seller =
+            RBACModule::add_role_in_range(1000, "seller".as_bytes(), &[], true).unwrap();
+        let role_id_buyer =
+            RBACModule::add_role_in_range(1000, "buyer".as_bytes(), &[role_id_seller], false)
+                .unwrap();
+        let role_id_voter =
+            RBACModule::add_role_in_range(2000, "voter".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id_seller, 1000);
+        assert_eq!(role_id_buyer, 1001);
+        assert_eq!(role_id_voter, 2000);
+        assert!(RBACModule::roles(role_id_buyer).is_some());
+
+        assert_eq!(
+            RBACModule::add_role_in_range(1000, "auditor".as_bytes(), &[], true),
+            Ok(1002)
+        );
+        assert_eq!(
+            RBACModule::add_role_in_range(1000, "auditor".as_bytes(), &[], true),
+            Err(InterfaceError::RangeExhausted)
+        );
+        assert_eq!(
+            RBACModule::add_role_in_range(3000, "auditor".as_bytes(), &[], true),
+            Err(InterfaceError::InvalidRange)
+        );
+
+        // Ids outside of the ranges are generated as usual
+        assert_eq!(RBACModule::add_role("admin".as_bytes(), &[], true), Ok(1));
+    });
+}
+
+// Generated ids skip the reserved ranges, including the free ids in them
+#[test]
+fn test_id_ranges_skipped() {
+    use crate::mock::sequential::{self, RoleId};
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(RBACModule::reserve_id_range(2, 4));
+        assert_ok!(RBACModule::reserve_id_range(5, 5));
+        assert_eq!(
+            RBACModule::add_role_in_range(2, "seller".as_bytes(), &[], true),
+            Ok(2)
+        );
+
+        assert_eq!(RBACModule::add_role("admin".as_bytes(), &[], true), Ok(1));
+        assert_eq!(RBACModule::add_role("user".as_bytes(), &[], true), Ok(6));
+        assert_eq!(RBACModule::add_role("guest".as_bytes(), &[], true), Ok(7));
+        assert_eq!(
+            RBACModule::add_role_in_range(2, "buyer".as_bytes(), &[], true),
+            Ok(3)
+        );
+    });
+
+    // The ids allocated from the counter skip the ranges as well
+    sequential::new_test_ext().execute_with(|| {
+        assert_ok!(sequential::RBACModule::reserve_id_range(2, 3));
+        assert_eq!(
+            sequential::RBACModule::add_role("admin".as_bytes(), &[], true),
+            Ok(RoleId(1))
+        );
+        assert_eq!(
+            sequential::RBACModule::add_role("user".as_bytes(), &[], true),
+            Ok(RoleId(4))
+        );
+    });
+}
+
+// Recycled ids within the reserved ranges are not reused by `add_role`
+#[test]
+fn test_id_ranges_not_recycled() {
+    new_test_ext().execute_with(|| {
+        RecycleRoleIds::set(true);
+        assert_ok!(RBACModule::reserve_id_range(100, 199));
+        let role_id_seller =
+            RBACModule::add_role_in_range(100, "seller".as_bytes(), &[], true).unwrap();
+        assert_ok!(RBACModule::purge_role(
+            RuntimeOrigin::root(),
+            role_id_seller
+        ));
+        assert_eq!(RBACModule::freed_ids().into_inner(), vec![role_id_seller]);
+
+        assert_eq!(RBACModule::add_role("admin".as_bytes(), &[], true), Ok(1));
+        assert!(RBACModule::freed_ids().is_empty());
+    });
+}
+
+// Call a consumer's extrinsic that accepts only the holders of the admin role
+#[test]
+fn test_role_holder_origin() {
+    use crate::{mock::consumer, Origin};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id_admin, ConsumerAdminRole::get());
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+
+        // Signed by the role holder
+        assert_ok!(Consumer::restricted(RuntimeOrigin::signed(1)));
+        assert_eq!(consumer::LastCaller::<Test>::get(), Some(1));
+
+        // Signed by the account without the role
+        assert_noop!(
+            Consumer::restricted(RuntimeOrigin::signed(2)),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Consumer::restricted(RuntimeOrigin::root()),
+            sp_runtime::DispatchError::BadOrigin
+        );
+
+        // Converted role holder origin
+        let origin = RBACModule::as_role_holder(RuntimeOrigin::signed(1), role_id_admin).unwrap();
+        assert_eq!(origin, Origin::RoleHolder(1, role_id_admin));
+        assert_ok!(Consumer::restricted(origin.into()));
+
+        // Role holder origin of another role is not accepted
+        let origin = RBACModule::as_role_holder(RuntimeOrigin::signed(2), role_id_user).unwrap();
+        assert_noop!(
+            Consumer::restricted(origin.into()),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            RBACModule::as_role_holder(RuntimeOrigin::signed(2), role_id_admin),
+            Error::<Test>::NotAuthorized
+        );
+    });
+}
+
+// Grant a role to the user holding a conflicting role
+#[test]
+fn test_grant_conflicting_role() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_buyer =
+            RBACModule::add_role("buyer".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_auditor =
+            RBACModule::add_role("auditor".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        let conflicts: BoundedVec<_, MaxConflicts> = vec![role_id_auditor].try_into().unwrap();
+        assert_ok!(RBACModule::set_conflicts(
+            RuntimeOrigin::root(),
+            role_id_buyer,
+            conflicts.clone()
+        ));
+        System::assert_last_event(
+            Event::ConflictsSet {
+                role_id: role_id_buyer,
+                conflicts,
+            }
+            .into(),
+        );
+
+        // Buyer can't become an auditor
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(account_id_admin),
+            2,
+            role_id_buyer
+        ));
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(account_id_admin), 2, role_id_auditor),
+            Error::<Test>::ConflictingRole
+        );
+        assert_eq!(
+            RBACModule::conflicting_role(&2, role_id_auditor),
+            Some(role_id_buyer)
+        );
+
+        // Auditor can't become a buyer
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(account_id_admin),
+            3,
+            role_id_auditor
+        ));
+        let users: BoundedVec<_, BatchMaxLength> = vec![4, 3].try_into().unwrap();
+        assert_noop!(
+            RBACModule::grant_role_batch(
+                RuntimeOrigin::signed(account_id_admin),
+                users,
+                role_id_buyer
+            ),
+            Error::<Test>::ConflictingRole
+        );
+        assert_eq!(RBACModule::conflicting_role(&4, role_id_buyer), None);
+    });
+}
+
+// Conflicts are mirrored on the conflicting roles and removed from them
+#[test]
+fn test_set_conflicts_symmetric() {
+    use crate::WeightInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id_buyer = RBACModule::add_role("buyer".as_bytes(), &[], true).unwrap();
+        let role_id_auditor = RBACModule::add_role("auditor".as_bytes(), &[], true).unwrap();
+        let role_id_seller = RBACModule::add_role("seller".as_bytes(), &[], true).unwrap();
+        let conflicts_of = |role_id| RBACModule::roles(role_id).unwrap().conflicts.into_inner();
+
+        let conflicts: BoundedVec<_, MaxConflicts> =
+            vec![role_id_auditor, role_id_seller, role_id_auditor]
+                .try_into()
+                .unwrap();
+        assert_ok!(RBACModule::set_conflicts(
+            RuntimeOrigin::root(),
+            role_id_buyer,
+            conflicts
+        ));
+        assert_eq!(
+            conflicts_of(role_id_buyer),
+            vec![role_id_auditor, role_id_seller]
+        );
+        assert_eq!(conflicts_of(role_id_auditor), vec![role_id_buyer]);
+        assert_eq!(conflicts_of(role_id_seller), vec![role_id_buyer]);
+
+        let conflicts: BoundedVec<_, MaxConflicts> = vec![role_id_auditor].try_into().unwrap();
+        assert_ok!(RBACModule::set_conflicts(
+            RuntimeOrigin::root(),
+            role_id_buyer,
+            conflicts
+        ));
+        assert_eq!(conflicts_of(role_id_buyer), vec![role_id_auditor]);
+        assert_eq!(conflicts_of(role_id_auditor), vec![role_id_buyer]);
+        assert!(conflicts_of(role_id_seller).is_empty());
+
+        // Purged role is removed from the conflicts and is charged for unlinking them
+        let post_info = RBACModule::purge_role(RuntimeOrigin::root(), role_id_auditor).unwrap();
+        assert_eq!(post_info.actual_weight, Some(<()>::purge_role(1, 1, 0)));
+        assert!(conflicts_of(role_id_buyer).is_empty());
+
+        let conflicts: BoundedVec<_, MaxConflicts> = vec![role_id_buyer].try_into().unwrap();
+        assert_noop!(
+            RBACModule::set_conflicts(RuntimeOrigin::root(), role_id_buyer, conflicts.clone()),
+            Error::<Test>::InvalidConflict
+        );
+        assert_noop!(
+            RBACModule::set_conflicts(RuntimeOrigin::signed(1), role_id_seller, conflicts),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        let conflicts: BoundedVec<_, MaxConflicts> = vec![role_id_auditor].try_into().unwrap();
+        assert_noop!(
+            RBACModule::set_conflicts(RuntimeOrigin::root(), role_id_seller, conflicts),
+            Error::<Test>::RoleNotExist
+        );
+    });
+}
+
+// Every change of the role stamps it with the current block
+#[test]
+fn test_role_modified_at() {
+    new_test_ext().execute_with(|| {
+        let modified_at = |role_id| RBACModule::roles(role_id).unwrap().modified_at;
+
+        System::set_block_number(1);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        assert_eq!(modified_at(role_id_admin), 1);
+
+        System::set_block_number(5);
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            Some(10),
+            None
+        ));
+        assert_eq!(modified_at(role_id_user), 5);
+        assert_eq!(modified_at(role_id_admin), 1);
+
+        // Assignments don't change the role itself
+        System::set_block_number(6);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(modified_at(role_id_user), 5);
+
+        // Both sides of a conflict are changed
+        System::set_block_number(7);
+        let conflicts: BoundedVec<_, MaxConflicts> = vec![role_id_admin].try_into().unwrap();
+        assert_ok!(RBACModule::set_conflicts(
+            RuntimeOrigin::root(),
+            role_id_user,
+            conflicts
+        ));
+        assert_eq!(modified_at(role_id_user), 7);
+        assert_eq!(modified_at(role_id_admin), 7);
+    });
+}
+
+// Grant a role to the zero account when it is rejected
+#[test]
+fn test_grant_role_default_account() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        // Allowed by default
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            0,
+            role_id_user
+        ));
+
+        RejectDefaultAccount::set(true);
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 0, role_id_admin),
+            Error::<Test>::InvalidGrantee
+        );
+        let users: BoundedVec<_, BatchMaxLength> = vec![2, 0].try_into().unwrap();
+        assert_noop!(
+            RBACModule::grant_role_batch(RuntimeOrigin::signed(1), users, role_id_admin),
+            Error::<Test>::InvalidGrantee
+        );
+        assert_eq!(
+            RBACModule::preassign_role(0, role_id_admin),
+            Err(InterfaceError::InvalidGrantee)
+        );
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+    });
+}
+
+// Count the granters of the roles
+#[test]
+fn test_granter_count() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_manager =
+            RBACModule::add_role("manager".as_bytes(), &[role_id_admin], true).unwrap();
+        let role_id_user = RBACModule::add_role(
+            "user".as_bytes(),
+            &[role_id_admin, role_id_manager, role_id_admin],
+            false,
+        )
+        .unwrap();
+
+        for (role_id, count) in [(role_id_admin, 1), (role_id_manager, 2), (role_id_user, 2)] {
+            assert_eq!(RBACModule::granter_count(&role_id), count);
+            assert_eq!(
+                RBACModule::granter_count(&role_id) as usize,
+                RBACModule::roles(role_id).unwrap().granters.len()
+            );
+        }
+        assert_eq!(RBACModule::granter_count(&42), 0);
+    });
+}
+
+// Revoke a role from a batch of holders and non-holders
+#[test]
+fn test_revoke_role_batch() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        let account_id_admin = 1_u64;
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        for user in [2, 4] {
+            RBACModule::preassign_role(user, role_id_user).unwrap();
+        }
+
+        System::set_block_number(1);
+
+        let users: BoundedVec<_, BatchMaxLength> = vec![2, 3, 4, 5].try_into().unwrap();
+        assert_ok!(RBACModule::revoke_role_batch(
+            RuntimeOrigin::signed(account_id_admin),
+            users,
+            role_id_user
+        ));
+        System::assert_has_event(
+            Event::RoleRevoked {
+                user: 2,
+                role_id: role_id_user,
+                total_holders: 1,
+            }
+            .into(),
+        );
+        System::assert_has_event(
+            Event::RoleRevoked {
+                user: 4,
+                role_id: role_id_user,
+                total_holders: 0,
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::BatchRevokeCompleted {
+                role_id: role_id_user,
+                revoked: 2,
+                skipped: 2,
+            }
+            .into(),
+        );
+        assert!(RBACModule::assignment_info(2, role_id_user).is_none());
+        assert!(RBACModule::assignment_info(4, role_id_user).is_none());
+        assert_eq!(RBACModule::holder_count(role_id_user), 0);
+
+        let users: BoundedVec<_, BatchMaxLength> = vec![account_id_admin].try_into().unwrap();
+        assert_noop!(
+            RBACModule::revoke_role_batch(RuntimeOrigin::signed(2), users, role_id_admin),
+            Error::<Test>::NotAuthorizedToRevoke
+        );
+    });
+}
+
+// Enable and disable granting the role by its own holders
+#[test]
+fn test_set_self_assignable() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        let granters_of = |role_id| RBACModule::roles(role_id).unwrap().granters.into_inner();
+
+        let account_id_admin = 1_u64;
+        RBACModule::preassign_role(account_id_admin, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+
+        System::set_block_number(1);
+
+        // User can't grant the role until it is self-assignable
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(2), 3, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        assert_ok!(RBACModule::set_self_assignable(
+            RuntimeOrigin::signed(account_id_admin),
+            role_id_user,
+            true
+        ));
+        System::assert_last_event(
+            Event::SelfAssignableChanged {
+                role_id: role_id_user,
+                enabled: true,
+            }
+            .into(),
+        );
+        assert_eq!(granters_of(role_id_user), vec![role_id_admin, role_id_user]);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(2),
+            3,
+            role_id_user
+        ));
+
+        // Enabling it twice doesn't duplicate the granter
+        assert_ok!(RBACModule::set_self_assignable(
+            RuntimeOrigin::signed(2),
+            role_id_user,
+            true
+        ));
+        assert_eq!(granters_of(role_id_user), vec![role_id_admin, role_id_user]);
+
+        assert_ok!(RBACModule::set_self_assignable(
+            RuntimeOrigin::signed(2),
+            role_id_user,
+            false
+        ));
+        assert_eq!(granters_of(role_id_user), vec![role_id_admin]);
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(2), 4, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+    });
+}
+
+// Disable granting the role by itself when it is its own only granter
+#[test]
+fn test_set_self_assignable_last_granter() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        assert_noop!(
+            RBACModule::set_self_assignable(RuntimeOrigin::signed(1), role_id_admin, false),
+            Error::<Test>::CannotRemoveLastGranter
+        );
+        assert_noop!(
+            RBACModule::set_self_assignable(RuntimeOrigin::signed(2), role_id_admin, true),
+            Error::<Test>::NotAuthorized
+        );
+    });
+}
+
+// Authorization checks are counted only when the metrics are tracked
+#[test]
+fn test_authorize_metrics() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        System::set_block_number(1);
+        assert_ok!(RBACModule::add_permission(
+            RuntimeOrigin::signed(1),
+            role_id_admin,
+            10
+        ));
+
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+        assert!(!RBACModule::authorize(&2, &[role_id_admin]));
+        assert_eq!(RBACModule::authorize_calls(), 0);
+        assert_eq!(RBACModule::authorize_successes(), 0);
+
+        TrackAuthorizeMetrics::set(true);
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+        assert!(!RBACModule::authorize(&2, &[role_id_admin]));
+        assert!(RBACModule::authorize_ordered(
+            &1,
+            &[role_id_admin],
+            &[role_id_admin]
+        ));
+        // Permission checks are counted too
+        assert!(RBACModule::authorize_permission(&1, &10));
+        assert!(!RBACModule::authorize_permission(&2, &10));
+        assert_eq!(RBACModule::authorize_calls(), 5);
+        assert_eq!(RBACModule::authorize_successes(), 3);
+    });
+}
+
+// Owner manages the role without holding any of its granters and transfers the ownership
+#[test]
+fn test_role_owner() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let owner = 5_u64;
+        let role_id_user =
+            RBACModule::add_owned_role(owner, "user".as_bytes(), &[role_id_admin], false).unwrap();
+        assert_eq!(RBACModule::roles(role_id_user).unwrap().owner, Some(owner));
+
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(owner),
+            role_id_user,
+            Some(2),
+            None
+        ));
+        assert_ok!(RBACModule::set_self_assignable(
+            RuntimeOrigin::signed(owner),
+            role_id_user,
+            true
+        ));
+
+        // Ownership doesn't allow granting the role
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(owner), 2, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+
+        assert_noop!(
+            RBACModule::transfer_role_ownership(RuntimeOrigin::signed(2), role_id_user, 2),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(RBACModule::transfer_role_ownership(
+            RuntimeOrigin::signed(owner),
+            role_id_user,
+            6
+        ));
+        System::assert_last_event(
+            Event::RoleOwnershipTransferred {
+                role_id: role_id_user,
+                old_owner: owner,
+                new_owner: 6,
+            }
+            .into(),
+        );
+        assert_noop!(
+            RBACModule::set_role_window(RuntimeOrigin::signed(owner), role_id_user, None, None),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(6),
+            role_id_user,
+            None,
+            None
+        ));
+
+        // Roles without an owner can't be transferred
+        assert_noop!(
+            RBACModule::transfer_role_ownership(RuntimeOrigin::signed(owner), role_id_admin, 6),
+            Error::<Test>::NotAuthorized
+        );
+    });
+}
+
+// Get the mask of the roles the user is authorized by
+#[test]
+fn test_authorize_mask() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_guest =
+            RBACModule::add_role("guest".as_bytes(), &[role_id_admin], false).unwrap();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(1, role_id_guest).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+        RBACModule::preassign_role(2, role_id_guest).unwrap();
+
+        let roles = [role_id_admin, role_id_user, role_id_guest];
+        assert_eq!(RBACModule::authorize_mask(&1, &roles), 0b101);
+        assert_eq!(RBACModule::authorize_mask(&2, &roles), 0b110);
+        assert_eq!(RBACModule::authorize_mask(&3, &roles), 0);
+        assert_eq!(RBACModule::authorize_mask(&1, &[]), 0);
+
+        // Denied role is not matched, unlike `authorize` the others still are
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(RBACModule::authorize_mask(&2, &roles), 0b100);
+
+        // Bits follow the positions in the list, roles after the 64th are ignored
+        let mut roles = vec![role_id_user; 70];
+        roles[63] = role_id_admin;
+        roles[64] = role_id_admin;
+        assert_eq!(RBACModule::authorize_mask(&1, &roles), 1 << 63);
+
+        assert_ok!(RBACModule::suspend_account(RuntimeOrigin::root(), 1));
+        assert_eq!(RBACModule::authorize_mask(&1, &[role_id_admin]), 0);
+    });
+}
+
+// Preassign and grant roles past the per-account cap
+#[test]
+fn test_max_roles_per_account() {
+    new_test_ext().execute_with(|| {
+        let max = MaxRolesPerAccount::get();
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let roles: Vec<_> = (0..max)
+            .map(|_| RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap())
+            .collect();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        for role_id in roles.iter() {
+            RBACModule::preassign_role(2, *role_id).unwrap();
+        }
+        assert_eq!(RBACModule::account_role_count(2), max);
+        assert_eq!(
+            RBACModule::preassign_role(2, role_id_admin),
+            Err(InterfaceError::TooManyRolesForAccount { max })
+        );
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id_admin),
+            Error::<Test>::TooManyRolesForAccount
+        );
+
+        // Roles already held don't count against the cap
+        assert_ok!(RBACModule::preassign_role(2, roles[0]));
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            roles[0]
+        ));
+
+        // Revoked role frees a slot
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            roles[0]
+        ));
+        assert_eq!(RBACModule::account_role_count(2), max - 1);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+    });
+}
+
+// Weights can be picked per instance, `I` defaults to `()` for the non-instanced pallet
+#[test]
+fn test_weights_per_instance() {
+    use crate::{weights::SubstrateWeight, WeightInfo};
+
+    struct Instance1;
+    struct Instance2;
+
+    fn weight_of<W: WeightInfo>() -> frame_support::weights::Weight {
+        W::grant_role(GrantersListMaxLength::get())
+    }
+
+    assert_eq!(
+        weight_of::<SubstrateWeight<Test, Instance1>>(),
+        weight_of::<SubstrateWeight<Test, Instance2>>()
+    );
+    assert_eq!(
+        weight_of::<SubstrateWeight<Test>>(),
+        weight_of::<SubstrateWeight<Test, ()>>()
+    );
+}
+
+// Grant and revoke a role passing the lookup source of the user
+#[test]
+fn test_grant_role_lookup_source() {
+    use sp_runtime::traits::StaticLookup;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        let source = <Test as frame_system::Config>::Lookup::unlookup(2);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            source,
+            role_id_admin
+        ));
+        assert!(RBACModule::assignment_info(2, role_id_admin).is_some());
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            source,
+            role_id_admin
+        ));
+        assert!(RBACModule::assignment_info(2, role_id_admin).is_none());
+    });
+}
+
+// Plan the grants and revocations reaching the desired set of roles
+#[test]
+fn test_reconcile_plan() {
+    new_test_ext().execute_with(|| {
+        let roles: Vec<_> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| RBACModule::add_role(name.as_bytes(), &[], true).unwrap())
+            .collect();
+        let (a, b, c, d) = (roles[0], roles[1], roles[2], roles[3]);
+
+        RBACModule::preassign_role(1, a).unwrap();
+        RBACModule::preassign_role(1, b).unwrap();
+        RBACModule::preassign_role(2, a).unwrap();
+
+        let (mut to_grant, mut to_revoke) = RBACModule::reconcile_plan(&1, &[b, c, d, c]);
+        to_grant.sort();
+        to_revoke.sort();
+        assert_eq!(to_grant, vec![c, d]);
+        assert_eq!(to_revoke, vec![a]);
+
+        assert_eq!(RBACModule::reconcile_plan(&1, &[a, b]), (vec![], vec![]));
+        assert_eq!(RBACModule::reconcile_plan(&3, &[a]), (vec![a], vec![]));
+
+        let (to_grant, mut to_revoke) = RBACModule::reconcile_plan(&1, &[]);
+        to_revoke.sort();
+        assert!(to_grant.is_empty());
+        assert_eq!(to_revoke, vec![a, b]);
+    });
+}
+
+// Roles created at genesis and by `add_role_silent` don't deposit events, runtime ones do
+#[test]
+fn test_genesis_roles_silent() {
+    genesis_ext(crate::GenesisConfig {
+        roles: vec![(b"admin".to_vec(), vec![], true)],
+        assignments: vec![(1, 1)],
+        granters: vec![],
+    })
+    .execute_with(|| {
+        assert!(System::events().is_empty());
+
+        System::set_block_number(1);
+        let role_id_silent = RBACModule::add_role_silent("silent".as_bytes(), &[1], false).unwrap();
+        assert!(RBACModule::roles(role_id_silent).is_some());
+        assert!(System::events().is_empty());
+
+        let role_id_user = RBACModule::add_role("user".as_bytes(), &[1], false).unwrap();
+        System::assert_last_event(
+            Event::RoleCreated {
+                id: role_id_user,
+                info: RBACModule::roles(role_id_user).unwrap(),
+            }
+            .into(),
+        );
+        assert_eq!(System::events().len(), 1);
+    });
+}
+
+// Dump all assignments of the active roles
+#[test]
+fn test_all_assignments() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_later =
+            RBACModule::add_role("later".as_bytes(), &[role_id_admin], false).unwrap();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(1, role_id_user).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+        RBACModule::preassign_role(3, role_id_user).unwrap();
+        RBACModule::preassign_role(3, role_id_later).unwrap();
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id_later,
+            Some(10),
+            None
+        ));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user
+        ));
+
+        let mut assignments = RBACModule::all_assignments();
+        assignments.sort();
+        assert_eq!(
+            assignments,
+            vec![(1, role_id_admin), (1, role_id_user), (2, role_id_user)]
+        );
+    });
+}
+
+// First generated role id follows the configured start
+#[test]
+fn test_role_id_start() {
+    new_test_ext().execute_with(|| {
+        RoleIdStart::set(100);
+        assert_eq!(RBACModule::add_role("admin".as_bytes(), &[], true), Ok(101));
+        assert_eq!(
+            RBACModule::add_role("user".as_bytes(), &[101], false),
+            Ok(102)
+        );
+    });
+}
+
+// Role granted from a future block authorizes the user only after it
+#[test]
+fn test_grant_role_from() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role_from(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user,
+            5
+        ));
+        System::assert_has_event(
+            Event::RoleGranted {
+                user: 2,
+                role_id: role_id_user,
+                newly_granted: true,
+                total_holders: 1,
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::RoleGrantScheduled {
+                user: 2,
+                role_id: role_id_user,
+                active_from: 5,
+            }
+            .into(),
+        );
+
+        // Before activation
+        assert!(RBACModule::assignment_info(2, role_id_user).is_some());
+        assert!(!RBACModule::authorize(&2, &[role_id_user]));
+        System::set_block_number(4);
+        assert!(!RBACModule::authorize(&2, &[role_id_user]));
+
+        // After activation
+        System::set_block_number(5);
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+
+        // Role window applies too
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            None,
+            Some(7)
+        ));
+        System::set_block_number(8);
+        assert!(!RBACModule::authorize(&2, &[role_id_user]));
+    });
+}
+
+// Regular grant activates a scheduled role right away
+#[test]
+fn test_grant_role_activates_scheduled() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role_from(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin,
+            10
+        ));
+        assert!(!RBACModule::authorize(&2, &[role_id_admin]));
+
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+        assert_eq!(RBACModule::assignment_active_from(2, role_id_admin), None);
+        assert!(RBACModule::authorize(&2, &[role_id_admin]));
+
+        assert_noop!(
+            RBACModule::grant_role_from(RuntimeOrigin::signed(3), 4, role_id_admin, 10),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+    });
+}
+
+// Consumer bound on the facade can use all of the bundled traits
+#[test]
+fn test_role_provider_facade() {
+    fn setup_and_check<R: RoleProvider<u64, u32>>() -> bool {
+        let role_id = R::add_role("admin".as_bytes(), &[], true).unwrap();
+        R::preassign_role(1, role_id).unwrap();
+        R::authorize(&1, &[role_id])
+    }
+
+    new_test_ext().execute_with(|| {
+        assert!(setup_and_check::<RBACModule>());
+    });
+}
+
+// Migration converts the version 0 roles and removes duplicated granters seeded by the earlier versions
+#[test]
+fn test_migration_dedup_granters() {
+    use crate::migrations::{v0, v1::DedupGranters};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user = RBACModule::add_role("user".as_bytes(), &[role_id_admin], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        // Seed the roles as they were stored before
+        let old_role = |name: &str, granters: Vec<u32>| v0::RoleInfo {
+            name: name.as_bytes().to_vec().try_into().unwrap(),
+            granters: granters.try_into().unwrap(),
+        };
+        v0::Roles::<Test>::insert(role_id_admin, old_role("admin", vec![role_id_admin]));
+        v0::Roles::<Test>::insert(
+            role_id_user,
+            old_role(
+                "user",
+                vec![role_id_admin, role_id_user, role_id_admin, role_id_user],
+            ),
+        );
+        StorageVersion::new(0).put::<RBACModule>();
+        assert_eq!(RBACModule::roles(role_id_admin), None);
+
+        DedupGranters::<Test>::on_runtime_upgrade();
+
+        let role = RBACModule::roles(role_id_user).unwrap();
+        assert_eq!(role.name.into_inner(), b"user".to_vec());
+        assert_eq!((role.active_from, role.active_to), (None, None));
+        assert!(role.conflicts.is_empty());
+        assert_eq!(role.modified_at, 0);
+        assert_eq!(role.owner, None);
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+
+        assert_eq!(
+            RBACModule::roles(role_id_user)
+                .unwrap()
+                .granters
+                .into_inner(),
+            vec![role_id_admin, role_id_user]
+        );
+        assert_eq!(
+            RBACModule::roles(role_id_admin)
+                .unwrap()
+                .granters
+                .into_inner(),
+            vec![role_id_admin]
+        );
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(1)
+        );
+        assert_ok!(RBACModule::do_try_state());
+    });
+}
+
+// Migration keeps every role, checked by the try-runtime hooks
+#[cfg(feature = "try-runtime")]
+#[test]
+fn test_migration_dedup_granters_try_runtime() {
+    use crate::migrations::{v0, v1::DedupGranters};
+    use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        for role_id in 1..=3_u32 {
+            v0::Roles::<Test>::insert(
+                role_id,
+                v0::RoleInfo {
+                    name: b"role".to_vec().try_into().unwrap(),
+                    granters: vec![role_id, role_id].try_into().unwrap(),
+                },
+            );
+        }
+        StorageVersion::new(0).put::<RBACModule>();
+
+        let state = DedupGranters::<Test>::pre_upgrade().unwrap();
+        DedupGranters::<Test>::on_runtime_upgrade();
+        assert_ok!(DedupGranters::<Test>::post_upgrade(state));
+    });
+}
+
+// Audit log keeps the latest actions overwriting the oldest ones
+#[test]
+fn test_audit_log_wraparound() {
+    use crate::{AuditAction, AuditEntry};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        let entry = |block, user, action| AuditEntry {
+            block,
+            actor: 1,
+            user,
+            role_id: role_id_admin,
+            action,
+        };
+
+        // Preassignments are not recorded
+        assert!(RBACModule::audit_log().is_empty());
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+        System::set_block_number(2);
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+        assert_eq!(
+            RBACModule::audit_log(),
+            vec![
+                entry(1, 2, AuditAction::Grant),
+                entry(2, 2, AuditAction::Revoke)
+            ]
+        );
+
+        for (block, user) in [(3, 3), (4, 4), (5, 5)] {
+            System::set_block_number(block);
+            assert_ok!(RBACModule::grant_role(
+                RuntimeOrigin::signed(1),
+                user,
+                role_id_admin
+            ));
+        }
+        let log = RBACModule::audit_log();
+        assert_eq!(log.len(), AuditLogSize::get() as usize);
+        assert_eq!(
+            log,
+            vec![
+                entry(3, 3, AuditAction::Grant),
+                entry(4, 4, AuditAction::Grant),
+                entry(5, 5, AuditAction::Grant)
+            ]
+        );
+    });
+}
+
+// Authorize the user for the permissions attached to its roles
+#[test]
+fn test_authorize_permission() {
+    new_test_ext().execute_with(|| {
+        let (read, write, delete) = (10_u32, 11_u32, 12_u32);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_reader =
+            RBACModule::add_role("reader".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_reader).unwrap();
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::add_permission(
+            RuntimeOrigin::signed(1),
+            role_id_reader,
+            read
+        ));
+        System::assert_last_event(
+            Event::PermissionAdded {
+                role_id: role_id_reader,
+                permission: read,
+            }
+            .into(),
+        );
+        for permission in [read, write] {
+            assert_ok!(RBACModule::add_permission(
+                RuntimeOrigin::signed(1),
+                role_id_admin,
+                permission
+            ));
+        }
+
+        assert!(RBACModule::authorize_permission(&2, &read));
+        assert!(!RBACModule::authorize_permission(&2, &write));
+        assert!(RBACModule::authorize_permission(&1, &write));
+        assert!(!RBACModule::authorize_permission(&1, &delete));
+        assert!(!RBACModule::authorize_permission(&3, &read));
+
+        // Permissions are bounded, a duplicate doesn't take a slot
+        assert_ok!(RBACModule::add_permission(
+            RuntimeOrigin::signed(1),
+            role_id_admin,
+            read
+        ));
+        assert_noop!(
+            RBACModule::add_permission(RuntimeOrigin::signed(1), role_id_admin, delete),
+            Error::<Test>::TooManyPermissions
+        );
+
+        // Only granters can change the permissions
+        assert_noop!(
+            RBACModule::add_permission(RuntimeOrigin::signed(2), role_id_reader, write),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_ok!(RBACModule::remove_permission(
+            RuntimeOrigin::signed(1),
+            role_id_reader,
+            read
+        ));
+        System::assert_last_event(
+            Event::PermissionRemoved {
+                role_id: role_id_reader,
+                permission: read,
+            }
+            .into(),
+        );
+        assert!(!RBACModule::authorize_permission(&2, &read));
+
+        // Denied role doesn't give its permissions
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(1),
+            1,
+            role_id_admin
+        ));
+        assert!(!RBACModule::authorize_permission(&1, &write));
+    });
+}
+
+// Enumerations are sorted regardless of the insertion order
+#[test]
+fn test_sorted_enumerations() {
+    new_test_ext().execute_with(|| {
+        let roles: Vec<_> = (0..4)
+            .map(|_| RBACModule::add_role("role".as_bytes(), &[], true).unwrap())
+            .collect();
+
+        for role_id in roles.iter().rev() {
+            RBACModule::preassign_role(7, *role_id).unwrap();
+        }
+        for user in [9, 3, 5, 1] {
+            RBACModule::preassign_role(user, roles[1]).unwrap();
+        }
+
+        assert_eq!(RBACModule::roles_of(&7), roles);
+        assert_eq!(RBACModule::role_holders(roles[1]), vec![1, 3, 5, 7, 9]);
+        assert_eq!(RBACModule::role_holders(42), Vec::<u64>::new());
+
+        let assignments = RBACModule::all_assignments();
+        let mut sorted = assignments.clone();
+        sorted.sort();
+        assert_eq!(assignments, sorted);
+        assert_eq!(assignments.len(), 8);
+    });
+}
+
+// Revoke roles with one and several granters and check that only the actual granters are charged
+#[test]
+fn test_revoke_role_weight() {
+    use crate::WeightInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_moderator =
+            RBACModule::add_role("moderator".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_single =
+            RBACModule::add_role("single".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_many = RBACModule::add_role(
+            "many".as_bytes(),
+            &[role_id_moderator, role_id_admin],
+            false,
+        )
+        .unwrap();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_single).unwrap();
+        RBACModule::preassign_role(2, role_id_many).unwrap();
+
+        let single = RBACModule::revoke_role(RuntimeOrigin::signed(1), 2, role_id_single).unwrap();
+        let many = RBACModule::revoke_role(RuntimeOrigin::signed(1), 2, role_id_many).unwrap();
+        assert_eq!(single.actual_weight, Some(<()>::revoke_role(1)));
+        assert_eq!(many.actual_weight, Some(<()>::revoke_role(2)));
+        assert!(single.actual_weight.unwrap().ref_time() < many.actual_weight.unwrap().ref_time());
+
+        // the original granter skips the authorization
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_single
+        ));
+        let own = RBACModule::revoke_role(RuntimeOrigin::signed(1), 3, role_id_single).unwrap();
+        assert_eq!(own.actual_weight, Some(<()>::revoke_role(0)));
+    });
+}
+
+// Filter the calls by the roles of the dispatching account
+#[test]
+fn test_role_call_filter() {
+    use crate::RoleCallFilter;
+    use frame_support::traits::Contains;
+
+    type Filter = RoleCallFilter<Test, TestCallRoles>;
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("remarker".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id, RemarkRole::get());
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        let filtered = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+        let unfiltered = RuntimeCall::System(frame_system::Call::set_heap_pages { pages: 1 });
+
+        assert!(Filter::contains(&(1, filtered.clone())));
+        assert!(!Filter::contains(&(2, filtered)));
+        assert!(Filter::contains(&(1, unfiltered.clone())));
+        assert!(Filter::contains(&(2, unfiltered)));
+    });
+}
+
+// An account preassigned as a granter in genesis grants the role at runtime
+#[test]
+fn test_genesis_granters() {
+    genesis_ext(crate::GenesisConfig {
+        roles: vec![
+            (b"admin".to_vec(), vec![], true),
+            (b"moderator".to_vec(), vec![1], false),
+        ],
+        assignments: vec![],
+        granters: vec![(1, 1)],
+    })
+    .execute_with(|| {
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, 1));
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 3, 2));
+        assert!(RBACModule::authorize(&2, &[1]));
+        assert!(RBACModule::authorize(&3, &[2]));
+
+        // moderator is not its own granter, so its holders can't grant it
+        assert_eq!(
+            RBACModule::preassign_granter(3, 2),
+            Err(InterfaceError::NotSelfAssignable { role: 2 })
+        );
+        assert_eq!(
+            RBACModule::preassign_granter(3, 42),
+            Err(InterfaceError::RoleNotExist { role: 42 })
+        );
+    });
+}
+
+// Any change of the role changes its content hash
+#[test]
+fn test_role_content_hash() {
+    use sp_runtime::traits::BlakeTwo256;
+
+    let exists = |_: &u32| true;
+    let role = TestRoleInfo::try_new(10, b"admin", &[1], false, exists).unwrap();
+    let hash = role.content_hash::<BlakeTwo256>();
+    assert_eq!(role.clone().content_hash::<BlakeTwo256>(), hash);
+
+    let changes: Vec<fn(&mut TestRoleInfo)> = vec![
+        |role| role.name = b"root".to_vec().try_into().unwrap(),
+        |role| role.granters = vec![2].try_into().unwrap(),
+        |role| role.active_from = Some(1),
+        |role| role.active_to = Some(1),
+        |role| role.conflicts = vec![3].try_into().unwrap(),
+        |role| role.modified_at = 1,
+        |role| role.owner = Some(1),
+    ];
+    for change in changes {
+        let mut changed = role.clone();
+        change(&mut changed);
+        assert_ne!(changed.content_hash::<BlakeTwo256>(), hash);
+    }
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+        let hash = RBACModule::role_hash(role_id).unwrap();
+        assert_eq!(
+            hash,
+            RBACModule::roles(role_id)
+                .unwrap()
+                .content_hash::<BlakeTwo256>()
+        );
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id,
+            Some(1),
+            None
+        ));
+        assert_ne!(RBACModule::role_hash(role_id).unwrap(), hash);
+        assert_eq!(RBACModule::role_hash(42), None);
+    });
+}
+
+// A revoked role can't be granted again to the same user until the cooldown passes
+#[test]
+fn test_grant_role_flapping() {
+    new_test_ext().execute_with(|| {
+        FlapCooldown::set(5);
+        System::set_block_number(1);
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id
+        ));
+        assert_eq!(RBACModule::last_revoked(2, role_id), Some(1));
+
+        System::set_block_number(5);
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id),
+            Error::<Test>::Flapping
+        );
+        assert_noop!(
+            RBACModule::grant_role_batch(
+                RuntimeOrigin::signed(1),
+                vec![2].try_into().unwrap(),
+                role_id
+            ),
+            Error::<Test>::Flapping
+        );
+        // other users are not affected
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 3, role_id));
+
+        System::set_block_number(6);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert!(RBACModule::authorize(&2, &[role_id]));
+        assert_eq!(RBACModule::last_revoked(2, role_id), None);
+    });
+}
+
+// Zero cooldown doesn't track the revocations
+#[test]
+fn test_grant_role_flapping_disabled() {
+    new_test_ext().execute_with(|| {
+        FlapCooldown::set(0);
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id
+        ));
+        assert_eq!(RBACModule::last_revoked(2, role_id), None);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+    });
+}
+
+// Removing the role's own id from its granters is the same as forbidding it to grant itself
+#[test]
+fn test_remove_granter() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user = RBACModule::add_role("user".as_bytes(), &[role_id_admin], true).unwrap();
+        let granters_of = |role_id| RBACModule::roles(role_id).unwrap().granters.into_inner();
+
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::remove_granter(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            role_id_user
+        ));
+        System::assert_last_event(
+            Event::SelfAssignableChanged {
+                role_id: role_id_user,
+                enabled: false,
+            }
+            .into(),
+        );
+        assert_eq!(granters_of(role_id_user), vec![role_id_admin]);
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(2), 3, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+
+        assert_noop!(
+            RBACModule::remove_granter(RuntimeOrigin::signed(1), role_id_user, role_id_user),
+            Error::<Test>::GranterNotFound
+        );
+        assert_noop!(
+            RBACModule::remove_granter(RuntimeOrigin::signed(1), role_id_user, role_id_admin),
+            Error::<Test>::CannotRemoveLastGranter
+        );
+        assert_noop!(
+            RBACModule::remove_granter(RuntimeOrigin::signed(1), role_id_admin, role_id_admin),
+            Error::<Test>::CannotRemoveLastGranter
+        );
+
+        // Other granters are removed with their own event
+        assert_ok!(RBACModule::set_self_assignable(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            true
+        ));
+        assert_ok!(RBACModule::remove_granter(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            role_id_admin
+        ));
+        System::assert_last_event(
+            Event::GranterRemoved {
+                role_id: role_id_user,
+                granter: role_id_admin,
+            }
+            .into(),
+        );
+        assert_eq!(granters_of(role_id_user), vec![role_id_user]);
+    });
+}
+
+// Authorize holders and non-holders of the role at once
+#[test]
+fn test_authorize_many() {
+    let mut ext = new_test_ext();
+    let role_id = ext.execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+        RBACModule::preassign_role(3, role_id).unwrap();
+        RBACModule::preassign_role(4, role_id).unwrap();
+        assert_ok!(RBACModule::deny_role(RuntimeOrigin::signed(1), 4, role_id));
+
+        assert_eq!(
+            RBACModule::authorize_many(&[1, 2, 3, 4, 1], &role_id),
+            vec![true, false, true, false, true]
+        );
+        assert_eq!(RBACModule::authorize_many(&[1, 3], &42), vec![false, false]);
+        assert_eq!(
+            RBACModule::authorize_many(&[], &role_id),
+            Vec::<bool>::new()
+        );
+
+        role_id
+    });
+
+    // Repeated users don't read more than the distinct ones
+    let (authorized, distinct) =
+        with_read_nodes(&mut ext, || RBACModule::authorize_many(&[1, 3], &role_id));
+    assert_eq!(authorized, vec![true; 2]);
+    let (authorized, repeated) = with_read_nodes(&mut ext, || {
+        RBACModule::authorize_many(&[1, 1, 3, 1, 3], &role_id)
+    });
+    assert_eq!(authorized, vec![true; 5]);
+    assert_eq!(repeated, distinct);
+}
+
+// Set, overwrite and fetch the role metadata up to the cap
+#[test]
+fn test_set_role_metadata() {
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+        let key =
+            |bytes: &[u8]| -> crate::MetadataKeyOf<Test> { bytes.to_vec().try_into().unwrap() };
+        let value =
+            |bytes: &[u8]| -> crate::MetadataValueOf<Test> { bytes.to_vec().try_into().unwrap() };
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::set_role_metadata(
+                RuntimeOrigin::signed(2),
+                role_id,
+                key(b"rank"),
+                value(b"1")
+            ),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_ok!(RBACModule::set_role_metadata(
+            RuntimeOrigin::signed(1),
+            role_id,
+            key(b"rank"),
+            value(b"1")
+        ));
+        System::assert_last_event(
+            Event::RoleMetadataSet {
+                role_id,
+                key: key(b"rank"),
+                value: value(b"1"),
+            }
+            .into(),
+        );
+        assert_ok!(RBACModule::set_role_metadata(
+            RuntimeOrigin::signed(1),
+            role_id,
+            key(b"desc"),
+            value(b"administrator")
+        ));
+
+        // Overwriting an entry doesn't count towards the cap
+        assert_ok!(RBACModule::set_role_metadata(
+            RuntimeOrigin::signed(1),
+            role_id,
+            key(b"rank"),
+            value(b"2")
+        ));
+        assert_eq!(
+            RBACModule::role_metadata_count(role_id),
+            MaxMetadataPerRole::get()
+        );
+        assert_eq!(
+            RBACModule::role_metadata(role_id, key(b"rank")),
+            Some(value(b"2"))
+        );
+
+        assert_noop!(
+            RBACModule::set_role_metadata(
+                RuntimeOrigin::signed(1),
+                role_id,
+                key(b"tag"),
+                value(b"ops")
+            ),
+            Error::<Test>::TooManyMetadataEntries
+        );
+
+        let mut entries = RBACModule::role_metadata_entries(role_id);
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                (b"desc".to_vec(), b"administrator".to_vec()),
+                (b"rank".to_vec(), b"2".to_vec())
+            ]
+        );
+
+        // Purge removes the metadata with the role
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id));
+        assert!(RBACModule::role_metadata_entries(role_id).is_empty());
+        assert_eq!(RBACModule::role_metadata_count(role_id), 0);
+    });
+}
+
+// Find the first matching role of a lazily generated list
+#[test]
+fn test_authorize_first_match() {
+    new_test_ext().execute_with(|| {
+        let roles: Vec<_> = (0..4)
+            .map(|_| RBACModule::add_role("role".as_bytes(), &[], true).unwrap())
+            .collect();
+        RBACModule::preassign_role(1, roles[1]).unwrap();
+        RBACModule::preassign_role(1, roles[2]).unwrap();
+        RBACModule::preassign_role(1, roles[3]).unwrap();
+        assert_ok!(RBACModule::deny_role(RuntimeOrigin::signed(1), 1, roles[1]));
+
+        // Roles after the match are not generated
+        let generated = core::cell::Cell::new(0);
+        let lazy = roles
+            .iter()
+            .copied()
+            .inspect(|_| generated.set(generated.get() + 1));
+        assert_eq!(RBACModule::authorize_first_match(&1, lazy), Some(roles[2]));
+        assert_eq!(generated.get(), 3);
+
+        assert_eq!(
+            RBACModule::authorize_first_match(&2, roles.iter().copied()),
+            None
+        );
+        assert_eq!(
+            RBACModule::authorize_first_match(&1, core::iter::empty()),
+            None
+        );
+    });
+}
+
+// Authorize against and/or expressions over roles
+#[test]
+fn test_authorize_expr() {
+    use crate::RoleExpr::{And, Or, Role};
+
+    new_test_ext().execute_with(|| {
+        let roles: Vec<_> = (0..4)
+            .map(|_| RBACModule::add_role("role".as_bytes(), &[], true).unwrap())
+            .collect();
+        let (a, b, c, d) = (roles[0], roles[1], roles[2], roles[3]);
+        RBACModule::preassign_role(1, a).unwrap();
+        RBACModule::preassign_role(1, b).unwrap();
+        RBACModule::preassign_role(2, a).unwrap();
+        RBACModule::preassign_role(3, c).unwrap();
+
+        // (A and B) or C
+        let expr = Or(vec![And(vec![Role(a), Role(b)]), Role(c)]);
+        assert!(RBACModule::authorize_expr(&1, &expr));
+        assert!(!RBACModule::authorize_expr(&2, &expr));
+        assert!(RBACModule::authorize_expr(&3, &expr));
+        assert!(!RBACModule::authorize_expr(&4, &expr));
+
+        // A and (C or (B and not held D))
+        let expr = And(vec![
+            Role(a),
+            Or(vec![Role(c), And(vec![Role(b), Role(d)])]),
+        ]);
+        assert!(!RBACModule::authorize_expr(&1, &expr));
+        RBACModule::preassign_role(1, d).unwrap();
+        assert!(RBACModule::authorize_expr(&1, &expr));
+
+        // Denials apply to the single roles
+        assert_ok!(RBACModule::deny_role(RuntimeOrigin::signed(1), 1, b));
+        assert!(!RBACModule::authorize_expr(&1, &expr));
+
+        // Empty lists are never satisfied
+        assert!(!RBACModule::authorize_expr(&1, &And(vec![])));
+        assert!(!RBACModule::authorize_expr(&1, &Or(vec![])));
+    });
+}
+
+// Expressions over the depth or size limits are not authorized
+#[test]
+fn test_authorize_expr_bounded() {
+    use crate::RoleExpr::{self, Or, Role};
+
+    let mut ext = new_test_ext();
+    let role_id = ext.execute_with(|| {
+        let role_id = RBACModule::add_role("role".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        let nested = |depth| (0..depth).fold(Role(role_id), |expr, _| Or(vec![expr]));
+        assert!(RBACModule::authorize_expr(
+            &1,
+            &nested(RoleExpr::<u32>::MAX_DEPTH)
+        ));
+        assert!(!RBACModule::authorize_expr(
+            &1,
+            &nested(RoleExpr::<u32>::MAX_DEPTH + 1)
+        ));
+
+        let wide = |roles: u32| Or((0..roles).map(|_| Role(role_id)).collect());
+        assert!(RBACModule::authorize_expr(
+            &1,
+            &wide(RoleExpr::<u32>::MAX_NODES - 1)
+        ));
+        assert!(!RBACModule::authorize_expr(
+            &1,
+            &wide(RoleExpr::<u32>::MAX_NODES)
+        ));
+
+        role_id
+    });
+
+    // Oversized expressions are rejected before any role is checked
+    let wide = Or((0..RoleExpr::<u32>::MAX_NODES)
+        .map(|_| Role(role_id))
+        .collect());
+    let (authorized, read) = with_read_nodes(&mut ext, || RBACModule::authorize_expr(&1, &wide));
+    assert!(!authorized);
+    assert_eq!(read, 0);
+}
+
+// Migration converts the assignment flags and keeps the authorization unchanged
+#[test]
+fn test_migration_assignments_to_option_query() {
+    use crate::{
+        migrations::v2::{AssignmentInfo, Assignments as AssignmentsV2, AssignmentsToOptionQuery},
+        Assignments,
+    };
+    use frame_support::{
+        storage::unhashed,
+        traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+    };
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+        RBACModule::preassign_role(2, role_id).unwrap();
+
+        // Seed the flags as they were stored before
+        unhashed::put(&Assignments::<Test>::hashed_key_for(1, role_id), &true);
+        unhashed::put(&Assignments::<Test>::hashed_key_for(2, role_id), &false);
+        StorageVersion::new(1).put::<RBACModule>();
+
+        AssignmentsToOptionQuery::<Test>::on_runtime_upgrade();
+
+        assert_eq!(
+            AssignmentsV2::<Test>::get(1, role_id),
+            Some(AssignmentInfo { granted_at: 0 })
+        );
+        assert_eq!(AssignmentsV2::<Test>::get(2, role_id), None);
+        assert!(RBACModule::authorize(&1, &[role_id]));
+        assert!(!RBACModule::authorize(&2, &[role_id]));
+        assert!(!RBACModule::authorize(&3, &[role_id]));
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(2)
+        );
+
+        // The second run doesn't touch the converted entries
+        AssignmentsToOptionQuery::<Test>::on_runtime_upgrade();
+        assert!(RBACModule::authorize(&1, &[role_id]));
+    });
+}
+
+// Check the existence of many roles at once
+#[test]
+fn test_roles_exist() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user = RBACModule::add_role("user".as_bytes(), &[], true).unwrap();
+
+        assert_eq!(
+            RBACModule::roles_exist(&[role_id_user, 42, role_id_admin, role_id_user, 0]),
+            vec![true, false, true, true, false]
+        );
+        assert_eq!(RBACModule::roles_exist(&[]), Vec::<bool>::new());
+    });
+}
+
+// Check which of the roles the granter can grant at once
+#[test]
+fn test_grantable_by() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_hr = RBACModule::add_role("hr".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_staff = RBACModule::add_role("staff".as_bytes(), &[role_id_hr], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        assert_eq!(
+            RBACModule::grantable_by(&1, &[role_id_user, role_id_staff, 42, role_id_admin]),
+            vec![true, false, false, false]
+        );
+        assert_eq!(
+            RBACModule::grantable_by(&2, &[role_id_user, role_id_staff]),
+            vec![false, false]
+        );
+    });
+}
+
+// Empty role list is denied by default and allowed when configured
+#[test]
+fn test_authorize_empty_roles() {
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], false).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        EmptyRolesAllow::set(false);
+        assert!(!RBACModule::authorize(&1, &[]));
+        assert!(!RBACModule::authorize_ordered(&1, &[], &[role_id]));
+
+        EmptyRolesAllow::set(true);
+        assert!(RBACModule::authorize(&1, &[]));
+        assert!(RBACModule::authorize(&2, &[]));
+        assert!(RBACModule::authorize_ordered(&2, &[], &[role_id]));
+        assert!(!RBACModule::authorize(&2, &[role_id]));
+
+        // A role without granters still can't be granted and a prefix without matches still fails
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        assert!(!RBACModule::authorize_name_prefix(&1, b"moderator"));
+    });
+}
+
+// Ids of the purged roles are reused only after the purge is complete
+#[test]
+fn test_recycle_role_ids() {
+    new_test_ext().execute_with(|| {
+        RecycleRoleIds::set(true);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(9, role_id_admin).unwrap();
+        for user in 1..=4 {
+            RBACModule::preassign_role(user, role_id_user).unwrap();
+        }
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(9),
+            5,
+            role_id_user
+        ));
+
+        // Items are left after the first call, so the id is not freed yet
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert!(RBACModule::freed_ids().is_empty());
+        let role_id_fresh = RBACModule::add_role("fresh".as_bytes(), &[], true).unwrap();
+        assert_ne!(role_id_fresh, role_id_user);
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert_eq!(RBACModule::freed_ids().into_inner(), vec![role_id_user]);
+
+        // The new role gets the freed id without any of the old assignments
+        let role_id_new = RBACModule::add_role("new".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id_new, role_id_user);
+        assert!(RBACModule::freed_ids().is_empty());
+        assert_eq!(RBACModule::holder_count(role_id_new), 0);
+        assert!(!RBACModule::authorize(&1, &[role_id_new]));
+
+        // Without recycling the freed ids are not kept
+        RecycleRoleIds::set(false);
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_fresh));
+        assert!(RBACModule::freed_ids().is_empty());
+        assert_ne!(
+            RBACModule::add_role("other".as_bytes(), &[], true).unwrap(),
+            role_id_fresh
+        );
+    });
+}
+
+// Reject the transactions of the accounts without the role required for the call
+#[test]
+fn test_check_role_extension() {
+    use frame_support::sp_runtime::transaction_validity::InvalidTransaction;
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("remarker".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id, RemarkRole::get());
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        let filtered = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+        let unfiltered = RuntimeCall::System(frame_system::Call::set_heap_pages { pages: 1 });
+        let info = Default::default();
+        let extension = CheckRole::<Test, TestCallRoles>::new();
+
+        assert_ok!(extension.validate(&1, &filtered, &info, 0));
+        assert_eq!(
+            extension.validate(&2, &filtered, &info, 0),
+            Err(InvalidTransaction::Custom(crate::ROLE_REQUIRED).into())
+        );
+        assert_ok!(extension.validate(&2, &unfiltered, &info, 0));
+        assert_ok!(extension.clone().pre_dispatch(&1, &filtered, &info, 0));
+        assert!(extension.pre_dispatch(&2, &filtered, &info, 0).is_err());
+    });
+}
+
+// Assignments record the block at which they were granted
+#[test]
+fn test_assignment_granted_at() {
+    use crate::AssignmentInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+        assert_eq!(
+            RBACModule::assignment_info(1, role_id),
+            Some(AssignmentInfo {
+                granted_at: 0,
+                extrinsic_index: None
+            })
+        );
+
+        System::set_block_number(7);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert_eq!(
+            RBACModule::assignment_info(2, role_id),
+            Some(AssignmentInfo {
+                granted_at: 7,
+                extrinsic_index: None
+            })
+        );
+
+        // Granting the held role again keeps the original block
+        System::set_block_number(9);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert_eq!(
+            RBACModule::assignment_info(2, role_id),
+            Some(AssignmentInfo {
+                granted_at: 7,
+                extrinsic_index: None
+            })
+        );
+    });
+}
+
+// Check that adding a role is charged per granter
+#[test]
+fn test_add_role_weight_scales_with_granters() {
+    use crate::{weights::SubstrateWeight, WeightInfo};
+
+    let max = GrantersListMaxLength::get();
+    assert!(<()>::add_role(0).all_lt(<()>::add_role(max)));
+    assert!(SubstrateWeight::<Test>::add_role(1).all_lt(SubstrateWeight::<Test>::add_role(max)));
+}
+
+// Purge a role in several calls and check that consumers are notified about its removal once
+#[test]
+fn test_on_role_removed() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        for user in 10..20 {
+            RBACModule::preassign_role(user, role_id_user).unwrap();
+        }
+        FlapCooldown::set(5);
+        assert_eq!(removed_roles(), vec![]);
+
+        // The refused purge doesn't notify
+        assert_noop!(
+            RBACModule::purge_role(RuntimeOrigin::root(), role_id_user),
+            Error::<Test>::TooManyToPurge
+        );
+        assert_ok!(RBACModule::revoke_role_batch(
+            RuntimeOrigin::signed(1),
+            (10..16).collect::<Vec<_>>().try_into().unwrap(),
+            role_id_user,
+        ));
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert_eq!(removed_roles(), vec![role_id_user]);
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert_eq!(removed_roles(), vec![role_id_user]);
+        assert!(RBACModule::roles(role_id_admin).is_some());
+    });
+}
+
+// Grant roles, recompute the membership root at the end of the block and verify the proofs against it
+#[test]
+fn test_membership_proof() {
+    use crate::merkle;
+    use frame_support::traits::Hooks;
+    use sp_runtime::traits::BlakeTwo256;
+
+    new_test_ext().execute_with(|| {
+        TrackMembershipRoot::set(true);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        for user in 2..6 {
+            assert_ok!(RBACModule::grant_role(
+                RuntimeOrigin::signed(1),
+                user,
+                role_id_user
+            ));
+        }
+
+        RBACModule::on_finalize(1);
+        let root = RBACModule::membership_root();
+        assert_ne!(root, Default::default());
+        assert!(!crate::MembershipDirty::<Test>::get());
+
+        // Every assignment of the odd-sized tree is proven
+        for (user, role_id) in RBACModule::all_assignments() {
+            let proof = RBACModule::membership_proof(&user, role_id).unwrap();
+            let leaf = merkle::leaf_hash::<BlakeTwo256, _, _>(&user, &role_id);
+            assert!(merkle::verify::<BlakeTwo256>(&root, leaf, &proof));
+        }
+
+        // The proof doesn't fit another assignment and there is no proof for a missing one
+        let proof = RBACModule::membership_proof(&2, role_id_user).unwrap();
+        let leaf = merkle::leaf_hash::<BlakeTwo256, _, _>(&3_u64, &role_id_user);
+        assert!(!merkle::verify::<BlakeTwo256>(&root, leaf, &proof));
+        assert_eq!(RBACModule::membership_proof(&2, role_id_admin), None);
+
+        // The revocation changes the root at the end of the block
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        RBACModule::on_finalize(2);
+        assert_ne!(RBACModule::membership_root(), root);
+        assert_eq!(crate::MembershipSize::<Test>::get(), 4);
+    });
+}
+
+// A proof claiming another number of leaves doesn't verify, as the root commits to it
+#[test]
+fn test_membership_proof_leaf_count() {
+    use crate::merkle;
+    use sp_runtime::traits::BlakeTwo256;
+
+    let leaves: Vec<_> = (0..5_u64)
+        .map(|user| merkle::leaf_hash::<BlakeTwo256, _, _>(&user, &0_u32))
+        .collect();
+    let root = merkle::root::<BlakeTwo256>(&leaves);
+    let mut proof = merkle::proof::<BlakeTwo256>(&leaves, 1).unwrap();
+    assert!(merkle::verify::<BlakeTwo256>(&root, leaves[1], &proof));
+
+    proof.leaf_count = 4;
+    assert!(!merkle::verify::<BlakeTwo256>(&root, leaves[1], &proof));
+
+    // A single leaf is not its own root
+    assert_ne!(merkle::root::<BlakeTwo256>(&leaves[..1]), leaves[0]);
+}
+
+// The root is reset and no proofs are built if there are more than `MaxMembershipSize` assignments
+#[test]
+fn test_membership_root_bounded() {
+    use frame_support::traits::Hooks;
+
+    new_test_ext().execute_with(|| {
+        TrackMembershipRoot::set(true);
+        MaxMembershipSize::set(3);
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        RBACModule::on_finalize(1);
+        assert_ne!(RBACModule::membership_root(), Default::default());
+        assert_eq!(crate::MembershipSize::<Test>::get(), 2);
+
+        for user in 3..5 {
+            assert_ok!(RBACModule::grant_role(
+                RuntimeOrigin::signed(1),
+                user,
+                role_id_user
+            ));
+        }
+        RBACModule::on_finalize(2);
+        assert_eq!(RBACModule::membership_root(), Default::default());
+        assert_eq!(crate::MembershipSize::<Test>::get(), 0);
+        assert_eq!(RBACModule::membership_proof(&2, role_id_user), None);
+        assert_eq!(
+            RBACModule::on_initialize(3),
+            <() as crate::WeightInfo>::update_membership_root(3)
+        );
+    });
+}
+
+// Sponsor a user without an account and check that the account is reaped after the revocation
+#[test]
+fn test_sponsor_and_grant() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        assert!(!System::account_exists(&2));
+        assert_ok!(RBACModule::sponsor_and_grant(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        System::assert_last_event(
+            Event::AccountSponsored {
+                user: 2,
+                sponsor: 1,
+            }
+            .into(),
+        );
+        assert!(System::account_exists(&2));
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert!(!System::account_exists(&2));
+        assert_eq!(System::providers(&2), 0);
+        assert_eq!(RBACModule::sponsored(2, role_id_user), None);
+
+        // The account with a provider is not sponsored, so it keeps its provider after the revocation
+        System::inc_providers(&3);
+        assert_ok!(RBACModule::sponsor_and_grant(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user
+        ));
+        assert_eq!(System::providers(&3), 1);
+        System::assert_last_event(
+            Event::RoleGranted {
+                user: 3,
+                role_id: role_id_user,
+                newly_granted: true,
+                total_holders: 1,
+            }
+            .into(),
+        );
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user
+        ));
+        assert_eq!(System::providers(&3), 1);
+
+        // Purging the role releases the sponsored accounts too
+        assert_ok!(RBACModule::sponsor_and_grant(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert!(System::account_exists(&2));
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert!(!System::account_exists(&2));
+
+        assert_noop!(
+            RBACModule::sponsor_and_grant(RuntimeOrigin::signed(3), 4, role_id_admin),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        assert!(!System::account_exists(&4));
+    });
+}
+
+// Create the three-tier template and check that each tier is granted by the tier above it
+#[test]
+fn test_create_roles_from_template() {
+    new_test_ext().execute_with(|| {
+        let granters_of = |role_id| RBACModule::roles(role_id).unwrap().granters.into_inner();
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::create_roles_from_template(
+                RuntimeOrigin::signed(1),
+                RoleTemplate::AdminEditorViewer,
+                1
+            ),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_ok!(RBACModule::create_roles_from_template(
+            RuntimeOrigin::root(),
+            RoleTemplate::AdminEditorViewer,
+            1
+        ));
+
+        let role_ids = RBACModule::roles_of(&1);
+        assert_eq!(role_ids.len(), 1);
+        let admin = role_ids[0];
+        let (editor, viewer) = (admin + 1, admin + 2);
+        System::assert_last_event(
+            Event::RolesCreatedFromTemplate {
+                template: RoleTemplate::AdminEditorViewer,
+                role_ids: vec![admin, editor, viewer],
+                admin: 1,
+            }
+            .into(),
+        );
+        assert_eq!(granters_of(admin), vec![admin]);
+        assert_eq!(granters_of(editor), vec![admin]);
+        assert_eq!(granters_of(viewer), vec![editor]);
+
+        // Admin grants editors, editors grant viewers, but not each other
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, editor));
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(2), 3, viewer));
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(2), 3, editor),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 3, viewer),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+    });
+}
+
+// List the roles of the user with an active and an expired role
+#[test]
+fn test_roles_of_including_expired() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_temp =
+            RBACModule::add_role("temp".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(1, role_id_temp).unwrap();
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id_temp,
+            None,
+            Some(5)
+        ));
+
+        System::set_block_number(10);
+
+        assert_eq!(RBACModule::roles_of(&1), vec![role_id_admin]);
+        assert_eq!(
+            RBACModule::roles_of_including_expired(&1),
+            vec![(role_id_admin, None), (role_id_temp, Some(5))]
+        );
+        assert_eq!(RBACModule::roles_of_including_expired(&2), vec![]);
+    });
+}
+
+// Listings respect the window of the assignment, not only the window of the role
+#[test]
+fn test_roles_of_assignment_window() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_temp =
+            RBACModule::add_role("temp".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role_windowed(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_temp,
+            1,
+            5
+        ));
+        assert_ok!(RBACModule::set_role_window(
+            RuntimeOrigin::signed(1),
+            role_id_temp,
+            None,
+            Some(20)
+        ));
+
+        assert_eq!(RBACModule::roles_of(&2), vec![role_id_temp]);
+        assert_eq!(RBACModule::role_holders(role_id_temp), vec![2]);
+
+        System::set_block_number(10);
+
+        assert_eq!(RBACModule::roles_of(&2), vec![]);
+        assert_eq!(RBACModule::roles_of_paged(&2, None, 10), vec![]);
+        assert_eq!(RBACModule::role_holders(role_id_temp), vec![]);
+        assert_eq!(
+            RBACModule::roles_of_including_expired(&2),
+            vec![(role_id_temp, Some(5))]
+        );
+    });
+}
+
+// Try to revoke the removed role from inside the notification and check that the counters stay consistent
+#[test]
+fn test_reentrant_revocation_on_role_removed() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        ReenterOnRoleRemoved::set(Some((1, 10)));
+
+        // The batch doesn't notify anybody, so nothing runs between its authorization and its grants
+        assert_ok!(RBACModule::grant_role_batch(
+            RuntimeOrigin::signed(1),
+            vec![10, 11].try_into().unwrap(),
+            role_id_user,
+        ));
+        assert_eq!(reentrant_revocations(), vec![]);
+        assert_eq!(RBACModule::account_role_count(10), 1);
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        assert_eq!(reentrant_revocations(), vec![false]);
+        assert_eq!(RBACModule::account_role_count(10), 0);
+        assert_eq!(RBACModule::account_role_count(11), 0);
+        assert!(RBACModule::authorize(&1, &[role_id_admin]));
+        assert_ok!(RBACModule::do_try_state());
+    });
+}
+
+// Revoke a prerequisite role and check that the roles depending on it are revoked too
+#[test]
+fn test_revoke_role_cascades_to_dependents() {
+    use crate::WeightInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_member =
+            RBACModule::add_role("member".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_voter =
+            RBACModule::add_role("voter".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_proposer =
+            RBACModule::add_role("proposer".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        assert_noop!(
+            RBACModule::set_dependencies(
+                RuntimeOrigin::root(),
+                role_id_voter,
+                vec![role_id_voter].try_into().unwrap()
+            ),
+            Error::<Test>::InvalidDependency
+        );
+        for dependent in [role_id_voter, role_id_proposer] {
+            assert_ok!(RBACModule::set_dependencies(
+                RuntimeOrigin::root(),
+                dependent,
+                vec![role_id_member].try_into().unwrap()
+            ));
+        }
+        assert_eq!(
+            RBACModule::role_dependents(role_id_member).into_inner(),
+            vec![role_id_voter, role_id_proposer]
+        );
+
+        for role_id in [role_id_member, role_id_voter] {
+            assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        }
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_voter
+        ));
+
+        let post_info =
+            RBACModule::revoke_role(RuntimeOrigin::signed(1), 2, role_id_member).unwrap();
+        System::assert_has_event(
+            Event::RoleRevoked {
+                user: 2,
+                role_id: role_id_member,
+                total_holders: 0,
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::RoleRevoked {
+                user: 2,
+                role_id: role_id_voter,
+                total_holders: 1,
+            }
+            .into(),
+        );
+        assert_eq!(
+            post_info.actual_weight,
+            Some(<()>::revoke_role(0).saturating_add(<()>::revoke_dependents(2)))
+        );
+        assert_eq!(RBACModule::roles_of(&2), vec![]);
+
+        // Other holders of the dependent role keep it
+        assert!(RBACModule::authorize(&3, &[role_id_voter]));
+
+        // Dropping the dependency stops the cascade
+        assert_ok!(RBACModule::set_dependencies(
+            RuntimeOrigin::root(),
+            role_id_voter,
+            BoundedVec::default()
+        ));
+        assert_eq!(
+            RBACModule::role_dependents(role_id_member).into_inner(),
+            vec![role_id_proposer]
+        );
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_member
+        ));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_member
+        ));
+        assert!(RBACModule::authorize(&3, &[role_id_voter]));
+    });
+}
+
+// Check the origin against the role passed as the argument
+#[test]
+fn test_ensure_role_arg() {
+    use crate::EnsureRoleArg;
+    use frame_support::traits::EnsureOriginWithArg;
+
+    type Ensure = EnsureRoleArg<Test>;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        assert_eq!(
+            Ensure::try_origin(RuntimeOrigin::signed(1), &role_id_admin).ok(),
+            Some(1)
+        );
+        let origin = RBACModule::as_role_holder(RuntimeOrigin::signed(1), role_id_admin).unwrap();
+        assert_eq!(
+            Ensure::try_origin(origin.into(), &role_id_admin).ok(),
+            Some(1)
+        );
+
+        // Wrong role and unsigned origins fail
+        assert!(Ensure::try_origin(RuntimeOrigin::signed(1), &role_id_user).is_err());
+        assert!(Ensure::try_origin(RuntimeOrigin::signed(2), &role_id_admin).is_err());
+        assert!(Ensure::try_origin(RuntimeOrigin::root(), &role_id_admin).is_err());
+        assert!(Ensure::try_origin(RuntimeOrigin::none(), &role_id_admin).is_err());
+    });
+}
+
+// Benchmarks get an origin of a role holder, the role is created if it doesn't exist
+#[cfg(feature = "runtime-benchmarks")]
+#[test]
+fn test_role_holder_successful_origin() {
+    use crate::{mock::ConsumerAdminRole, EnsureRoleArg, EnsureRoleHolder};
+    use frame_support::traits::{EnsureOrigin, EnsureOriginWithArg};
+
+    type Holder = EnsureRoleHolder<Test, ConsumerAdminRole>;
+    type Arg = EnsureRoleArg<Test>;
+
+    new_test_ext().execute_with(|| {
+        let origin: RuntimeOrigin = Holder::try_successful_origin().unwrap();
+        assert!(Holder::try_origin(origin).is_ok());
+
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let origin: RuntimeOrigin = Arg::try_successful_origin(&role_id_admin).unwrap();
+        assert!(Arg::try_origin(origin, &role_id_admin).is_ok());
+        assert_eq!(
+            RBACModule::roles(role_id_admin).unwrap().name.into_inner(),
+            b"admin".to_vec()
+        );
+    });
+}
+
+// Set, fetch and remove the pointer to the off-chain metadata of the role
+#[test]
+fn test_set_role_uri() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::set_role_uri(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            b"ipfs://Q".to_vec()
+        ));
+        System::assert_last_event(
+            Event::RoleUriSet {
+                role_id: role_id_user,
+                uri: b"ipfs://Q".to_vec().try_into().unwrap(),
+            }
+            .into(),
+        );
+        assert_eq!(
+            RBACModule::role_uri(role_id_user).map(|uri| uri.into_inner()),
+            Some(b"ipfs://Q".to_vec())
+        );
+
+        assert_noop!(
+            RBACModule::set_role_uri(
+                RuntimeOrigin::signed(1),
+                role_id_user,
+                b"ipfs://Qm".to_vec()
+            ),
+            Error::<Test>::UriTooLong
+        );
+        assert_noop!(
+            RBACModule::set_role_uri(RuntimeOrigin::signed(2), role_id_user, b"x".to_vec()),
+            Error::<Test>::NotAuthorized
+        );
+
+        assert_ok!(RBACModule::set_role_uri(
+            RuntimeOrigin::signed(1),
+            role_id_user,
+            vec![]
+        ));
+        assert_eq!(RBACModule::role_uri(role_id_user), None);
+
+        // The owner sets the pointer without holding any of the granters
+        let role_id_owned =
+            RBACModule::add_owned_role(5, "owned".as_bytes(), &[role_id_admin], false).unwrap();
+        assert_ok!(RBACModule::set_role_uri(
+            RuntimeOrigin::signed(5),
+            role_id_owned,
+            b"ipfs://Q".to_vec()
+        ));
+        assert!(RBACModule::role_uri(role_id_owned).is_some());
+    });
+}
+
+// Grant several roles with one of them conflicting, failing atomically or skipping it
+#[test]
+fn test_grant_roles_skip_conflicts() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_author =
+            RBACModule::add_role("author".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_editor =
+            RBACModule::add_role("editor".as_bytes(), &[role_id_admin], false).unwrap();
+        let role_id_reviewer =
+            RBACModule::add_role("reviewer".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_author).unwrap();
+        assert_ok!(RBACModule::set_conflicts(
+            RuntimeOrigin::root(),
+            role_id_reviewer,
+            vec![role_id_author].try_into().unwrap()
+        ));
+
+        System::set_block_number(1);
+
+        let roles: BoundedVec<_, BatchMaxLength> =
+            vec![role_id_editor, role_id_reviewer].try_into().unwrap();
+        assert_noop!(
+            RBACModule::grant_roles(RuntimeOrigin::signed(1), 2, roles.clone(), false),
+            Error::<Test>::ConflictingRole
+        );
+
+        assert_ok!(RBACModule::grant_roles(
+            RuntimeOrigin::signed(1),
+            2,
+            roles,
+            true
+        ));
+        System::assert_last_event(
+            Event::GrantPartial {
+                user: 2,
+                granted: vec![role_id_editor],
+                skipped_conflicts: vec![role_id_reviewer],
+            }
+            .into(),
+        );
+        assert_eq!(
+            RBACModule::roles_of(&2),
+            vec![role_id_author, role_id_editor]
+        );
+    });
+}
+
+// Assignments record the index of the extrinsic that granted them
+#[test]
+fn test_assignment_extrinsic_index() {
+    use crate::AssignmentInfo;
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        System::set_block_number(4);
+        System::set_extrinsic_index(3);
+        assert_ok!(RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id));
+        assert_eq!(
+            RBACModule::assignment_info(2, role_id),
+            Some(AssignmentInfo {
+                granted_at: 4,
+                extrinsic_index: Some(3)
+            })
+        );
+    });
+}
+
+// Migration adds an unknown extrinsic index to the existing assignments
+#[test]
+fn test_migration_assignment_extrinsic_index() {
+    use crate::{
+        migrations::{v2, v3::AssignmentExtrinsicIndex},
+        AssignmentInfo,
+    };
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        // Seed the assignment as it was stored before
+        v2::Assignments::<Test>::insert(1, role_id, v2::AssignmentInfo { granted_at: 5 });
+        StorageVersion::new(2).put::<RBACModule>();
+
+        AssignmentExtrinsicIndex::<Test>::on_runtime_upgrade();
+
+        assert_eq!(
+            RBACModule::assignment_info(1, role_id),
+            Some(AssignmentInfo {
+                granted_at: 5,
+                extrinsic_index: None
+            })
+        );
+        assert!(RBACModule::authorize(&1, &[role_id]));
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(3)
+        );
+    });
+}
+
+// Roles of the user can be listed page by page
+#[test]
+fn test_roles_of_paged() {
+    new_test_ext().execute_with(|| {
+        let mut role_ids = Vec::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            let role_id = RBACModule::add_role(name.as_bytes(), &[], true).unwrap();
+            RBACModule::preassign_role(1, role_id).unwrap();
+            role_ids.push(role_id);
+        }
+
+        let first = RBACModule::roles_of_paged(&1, None, 3);
+        assert_eq!(first.len(), 3);
+        let second = RBACModule::roles_of_paged(&1, first.last().copied(), 3);
+        assert_eq!(second.len(), 2);
+        assert!(RBACModule::roles_of_paged(&1, second.last().copied(), 3).is_empty());
+
+        let mut listed = [first, second].concat();
+        listed.sort();
+        assert_eq!(listed, role_ids);
+        assert!(RBACModule::roles_of_paged(&2, None, 3).is_empty());
+    });
+}
+
+// Consumer call gated by the macro runs only for the role holders
+#[test]
+fn test_role_gated_macro() {
+    new_test_ext().execute_with(|| {
+        let role_id = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        assert_eq!(role_id, ConsumerAdminRole::get());
+        RBACModule::preassign_role(1, role_id).unwrap();
+
+        assert_ok!(Consumer::gated(RuntimeOrigin::signed(1)));
+        assert_eq!(consumer::LastCaller::<Test>::get(), Some(1));
+
+        assert_noop!(
+            Consumer::gated(RuntimeOrigin::signed(2)),
+            consumer::Error::<Test>::NotAuthorized
+        );
+        assert_noop!(
+            Consumer::gated(RuntimeOrigin::root()),
+            sp_runtime::DispatchError::BadOrigin
+        );
+    });
+}
+
+// Windowed assignment authorizes the user only within its window
+#[test]
+fn test_grant_role_windowed() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+        assert_ok!(RBACModule::grant_role_windowed(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user,
+            3,
+            5
+        ));
+        System::assert_last_event(
+            Event::RoleGrantWindowed {
+                user: 2,
+                role_id: role_id_user,
+                active_from: 3,
+                active_to: 5,
+            }
+            .into(),
+        );
+
+        // Before the window
+        assert!(RBACModule::assignment_info(2, role_id_user).is_some());
+        assert!(!RBACModule::authorize(&2, &[role_id_user]));
+
+        // Within the window
+        System::set_block_number(3);
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+        System::set_block_number(5);
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+
+        // After the window the user still holds the role
+        System::set_block_number(6);
+        assert!(!RBACModule::authorize(&2, &[role_id_user]));
+        assert!(RBACModule::assignment_info(2, role_id_user).is_some());
+
+        // Regular grant removes the window
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(RBACModule::assignment_active_to(2, role_id_user), None);
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+
+        assert_noop!(
+            RBACModule::grant_role_windowed(RuntimeOrigin::signed(1), 3, role_id_user, 5, 4),
+            Error::<Test>::InvalidWindow
+        );
+    });
+}
+
+// Revoking a windowed assignment clears its window
+#[test]
+fn test_revoke_windowed_assignment() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        assert_ok!(RBACModule::grant_role_windowed(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user,
+            0,
+            5
+        ));
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(RBACModule::assignment_active_from(2, role_id_user), None);
+        assert_eq!(RBACModule::assignment_active_to(2, role_id_user), None);
+    });
+}
+
+// Revoking a role the user doesn't hold fails without an event
+#[test]
+fn test_revoke_role_not_granted() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+        assert_noop!(
+            RBACModule::revoke_role(RuntimeOrigin::signed(1), 2, role_id_user),
+            Error::<Test>::RoleNotGranted
+        );
+        assert!(System::events().is_empty());
+
+        // The authorization is still checked first
+        assert_noop!(
+            RBACModule::revoke_role(RuntimeOrigin::signed(3), 2, role_id_user),
+            Error::<Test>::NotAuthorizedToRevoke
+        );
+    });
+}
+
+// `()` weights have the same reads and writes as `SubstrateWeight` of a RocksDb runtime
+#[test]
+fn test_unit_weights_match_substrate_weights() {
+    use crate::{mock::small, weights::SubstrateWeight, WeightInfo};
+
+    type Weights = SubstrateWeight<small::Test>;
+    let (n, m) = (3, 5);
+
+    let weights = [
+        ("grant_role", Weights::grant_role(n), <()>::grant_role(n)),
+        ("revoke_role", Weights::revoke_role(n), <()>::revoke_role(n)),
+        ("add_role", Weights::add_role(n), <()>::add_role(n)),
+        ("authorize", Weights::authorize(n), <()>::authorize(n)),
+        (
+            "authorize_ordered",
+            Weights::authorize_ordered(),
+            <()>::authorize_ordered(),
+        ),
+        (
+            "authorize_name_prefix",
+            Weights::authorize_name_prefix(n, m),
+            <()>::authorize_name_prefix(n, m),
+        ),
+        (
+            "set_role_window",
+            Weights::set_role_window(),
+            <()>::set_role_window(),
+        ),
+        (
+            "grant_role_batch",
+            Weights::grant_role_batch(n, m, n),
+            <()>::grant_role_batch(n, m, n),
+        ),
+        (
+            "protect_role",
+            Weights::protect_role(),
+            <()>::protect_role(),
+        ),
+        (
+            "unprotect_role",
+            Weights::unprotect_role(),
+            <()>::unprotect_role(),
+        ),
+        ("deny_role", Weights::deny_role(), <()>::deny_role()),
+        ("undeny_role", Weights::undeny_role(), <()>::undeny_role()),
+        (
+            "purge_role",
+            Weights::purge_role(n, m, n),
+            <()>::purge_role(n, m, n),
+        ),
+        (
+            "suspend_account",
+            Weights::suspend_account(),
+            <()>::suspend_account(),
+        ),
+        (
+            "reinstate_account",
+            Weights::reinstate_account(),
+            <()>::reinstate_account(),
+        ),
+        (
+            "set_conflicts",
+            Weights::set_conflicts(n),
+            <()>::set_conflicts(n),
+        ),
+        (
+            "revoke_role_batch",
+            Weights::revoke_role_batch(n, m),
+            <()>::revoke_role_batch(n, m),
+        ),
+        (
+            "set_self_assignable",
+            Weights::set_self_assignable(n),
+            <()>::set_self_assignable(n),
+        ),
+        (
+            "transfer_role_ownership",
+            Weights::transfer_role_ownership(),
+            <()>::transfer_role_ownership(),
+        ),
+        (
+            "grant_role_from",
+            Weights::grant_role_from(n),
+            <()>::grant_role_from(n),
+        ),
+        (
+            "add_permission",
+            Weights::add_permission(),
+            <()>::add_permission(),
+        ),
+        (
+            "remove_permission",
+            Weights::remove_permission(),
+            <()>::remove_permission(),
+        ),
+        (
+            "remove_granter",
+            Weights::remove_granter(n),
+            <()>::remove_granter(n),
+        ),
+        (
+            "set_role_metadata",
+            Weights::set_role_metadata(),
+            <()>::set_role_metadata(),
+        ),
+        (
+            "update_membership_root",
+            Weights::update_membership_root(n),
+            <()>::update_membership_root(n),
+        ),
+        (
+            "sponsor_and_grant",
+            Weights::sponsor_and_grant(n),
+            <()>::sponsor_and_grant(n),
+        ),
+        (
+            "create_roles_from_template",
+            Weights::create_roles_from_template(),
+            <()>::create_roles_from_template(),
+        ),
+        (
+            "revoke_dependents",
+            Weights::revoke_dependents(n),
+            <()>::revoke_dependents(n),
+        ),
+        (
+            "set_dependencies",
+            Weights::set_dependencies(n),
+            <()>::set_dependencies(n),
+        ),
+        (
+            "set_role_uri",
+            Weights::set_role_uri(n),
+            <()>::set_role_uri(n),
+        ),
+        (
+            "grant_roles",
+            Weights::grant_roles(n, m),
+            <()>::grant_roles(n, m),
+        ),
+        (
+            "grant_role_windowed",
+            Weights::grant_role_windowed(n),
+            <()>::grant_role_windowed(n),
+        ),
+        (
+            "reap_expired_assignments",
+            Weights::reap_expired_assignments(n),
+            <()>::reap_expired_assignments(n),
+        ),
+        (
+            "force_preassign",
+            Weights::force_preassign(),
+            <()>::force_preassign(),
+        ),
+    ];
+    for (name, substrate, unit) in weights {
+        assert_eq!(substrate, unit, "`{name}` weights differ");
+    }
+}
+
+// Expired assignments are reaped in `on_idle` as far as the weight allows, resuming from the cursor
+#[test]
+fn test_reap_expired_assignments_on_idle() {
+    use crate::{AssignmentActiveTo, ReapCursor, WeightInfo};
+    use frame_support::{traits::Hooks, weights::Weight};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+
+        System::set_block_number(1);
+        for user in [2, 3, 4] {
+            assert_ok!(RBACModule::grant_role_windowed(
+                RuntimeOrigin::signed(1),
+                user,
+                role_id_user,
+                1,
+                5
+            ));
+        }
+        System::set_block_number(10);
+
+        // Not enough weight to visit a single assignment
+        let weight = <()>::reap_expired_assignments(1).saturating_sub(Weight::from_parts(1, 0));
+        assert_eq!(RBACModule::on_idle(10, weight), Weight::zero());
+        assert_eq!(AssignmentActiveTo::<Test>::iter().count(), 3);
+
+        // Partial reaping leaves the cursor at the last visited assignment
+        let weight = <()>::reap_expired_assignments(2);
+        assert_eq!(RBACModule::on_idle(10, weight), weight);
+        assert_eq!(AssignmentActiveTo::<Test>::iter().count(), 1);
+        assert_eq!(RBACModule::holder_count(role_id_user), 1);
+        let cursor = ReapCursor::<Test>::get().unwrap();
+        assert!(RBACModule::assignment_info(cursor.0, cursor.1).is_none());
+
+        // The next block resumes after the cursor and starts over once all are visited
+        assert_eq!(
+            RBACModule::on_idle(11, weight),
+            <()>::reap_expired_assignments(1)
+        );
+        assert_eq!(AssignmentActiveTo::<Test>::iter().count(), 0);
+        assert_eq!(RBACModule::holder_count(role_id_user), 0);
+        assert_eq!(ReapCursor::<Test>::get(), None);
+        System::assert_has_event(
+            Event::AssignmentExpired {
+                user: 4,
+                role_id: role_id_user,
+            }
+            .into(),
+        );
+
+        // Assignments within their window are kept
+        assert_ok!(RBACModule::grant_role_windowed(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user,
+            1,
+            20
+        ));
+        assert_ok!(RBACModule::grant_role_windowed(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user,
+            1,
+            5
+        ));
+        RBACModule::on_idle(12, Weight::MAX);
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+        assert!(RBACModule::assignment_info(3, role_id_user).is_none());
+    });
+}
+
+// Only `ForceOrigin` can preassign a role, and it skips the grant authorization
+#[test]
+fn test_force_preassign() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+
+        System::set_block_number(1);
+        assert_noop!(
+            RBACModule::force_preassign(RuntimeOrigin::signed(1), 2, role_id_user),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            RBACModule::force_preassign(RuntimeOrigin::root(), 2, 42),
+            Error::<Test>::RoleNotExist
+        );
+
+        // Nobody holds the granter of the role, so it can't be granted otherwise
+        assert_noop!(
+            RBACModule::grant_role(RuntimeOrigin::signed(1), 2, role_id_user),
+            Error::<Test>::NotAuthorizedToGrant
+        );
+        assert_ok!(RBACModule::force_preassign(
+            RuntimeOrigin::root(),
+            2,
+            role_id_user
+        ));
+        System::assert_last_event(
+            Event::RolePreassigned {
+                user: 2,
+                role_id: role_id_user,
+            }
+            .into(),
+        );
+        assert!(RBACModule::authorize(&2, &[role_id_user]));
+        assert_eq!(RBACModule::holder_count(role_id_user), 1);
+
+        // Recover the holder of a role nobody holds
+        assert_ok!(RBACModule::force_preassign(
+            RuntimeOrigin::root(),
+            1,
+            role_id_admin
+        ));
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user
+        ));
+    });
+}
+
+// The block of the grant can be looked up until the role is revoked
+#[test]
+fn test_granted_at() {
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        assert_eq!(RBACModule::granted_at(&1, role_id_admin), Some(0));
+
+        System::set_block_number(7);
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        System::set_block_number(9);
+        assert_eq!(RBACModule::granted_at(&2, role_id_user), Some(7));
+        assert_eq!(RBACModule::granted_at(&2, role_id_admin), None);
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(RBACModule::granted_at(&2, role_id_user), None);
+    });
+}
+
+// Migration indexes the existing assignments and denials by role, so the role can be purged
+#[test]
+fn test_migration_index_role_accounts() {
+    use crate::{migrations::v4::IndexRoleAccounts, RoleAccounts};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(1),
+            3,
+            role_id_user
+        ));
+
+        // Seed the storage as it was before the index
+        let _ = RoleAccounts::<Test>::clear(u32::MAX, None);
+        StorageVersion::new(3).put::<RBACModule>();
+
+        IndexRoleAccounts::<Test>::on_runtime_upgrade();
+
+        assert!(RoleAccounts::<Test>::contains_key(role_id_admin, 1));
+        assert!(RoleAccounts::<Test>::contains_key(role_id_user, 2));
+        assert!(RoleAccounts::<Test>::contains_key(role_id_user, 3));
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(4)
+        );
+
+        assert_ok!(RBACModule::purge_role(RuntimeOrigin::root(), role_id_user));
+        System::assert_last_event(
+            Event::RolePurged {
+                role_id: role_id_user,
+                removed: 3,
+                complete: true,
+            }
+            .into(),
+        );
+        assert!(RBACModule::assignment_info(2, role_id_user).is_none());
+        assert!(!RBACModule::denials(3, role_id_user));
+    });
+}
+
+// Revoked and undenied accounts leave the role index, so purge doesn't visit them
+#[test]
+fn test_role_accounts_follow_assignments() {
+    use crate::RoleAccounts;
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        System::set_block_number(1);
+
+        assert_ok!(RBACModule::grant_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_ok!(RBACModule::deny_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert!(RoleAccounts::<Test>::contains_key(role_id_user, 2));
+
+        assert_ok!(RBACModule::undeny_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert!(RoleAccounts::<Test>::contains_key(role_id_user, 2));
+
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_user
+        ));
+        assert_eq!(
+            RoleAccounts::<Test>::contains_key(role_id_user, 2),
+            RBACModule::last_revoked(2, role_id_user).is_some()
+        );
+        assert_ok!(RBACModule::do_try_state());
+    });
+}
+
+// Migration counts the holders of the roles granted before the counter
+#[test]
+fn test_migration_backfill_holder_count() {
+    use crate::{migrations::v5::BackfillHolderCount, HolderCount};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(2, role_id_admin).unwrap();
+        RBACModule::preassign_role(3, role_id_user).unwrap();
+        assert_ok!(RBACModule::protect_role(
+            RuntimeOrigin::root(),
+            role_id_admin
+        ));
+
+        // Seed the storage as it was before the counter
+        let _ = HolderCount::<Test>::clear(u32::MAX, None);
+        StorageVersion::new(4).put::<RBACModule>();
+        System::set_block_number(1);
+        assert_noop!(
+            RBACModule::revoke_role(RuntimeOrigin::signed(1), 2, role_id_admin),
+            Error::<Test>::CannotRemoveLastHolderOfProtectedRole
+        );
+
+        BackfillHolderCount::<Test>::on_runtime_upgrade();
+
+        assert_eq!(RBACModule::holder_count(role_id_admin), 2);
+        assert_eq!(RBACModule::holder_count(role_id_user), 1);
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(5)
+        );
+        assert_ok!(RBACModule::revoke_role(
+            RuntimeOrigin::signed(1),
+            2,
+            role_id_admin
+        ));
+        assert_eq!(RBACModule::holder_count(role_id_admin), 1);
+    });
+}
+
+// Migration counts the roles of the accounts that got them before the counter
+#[test]
+fn test_migration_backfill_account_role_count() {
+    use crate::{migrations::v6::BackfillAccountRoleCount, AccountRoleCount};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id_admin = RBACModule::add_role("admin".as_bytes(), &[], true).unwrap();
+        let role_id_user =
+            RBACModule::add_role("user".as_bytes(), &[role_id_admin], false).unwrap();
+        RBACModule::preassign_role(1, role_id_admin).unwrap();
+        RBACModule::preassign_role(1, role_id_user).unwrap();
+        RBACModule::preassign_role(2, role_id_user).unwrap();
+
+        // Seed the storage as it was before the counter
+        let _ = AccountRoleCount::<Test>::clear(u32::MAX, None);
+        StorageVersion::new(5).put::<RBACModule>();
+        assert!(RBACModule::do_try_state().is_err());
+
+        BackfillAccountRoleCount::<Test>::on_runtime_upgrade();
+
+        assert_eq!(RBACModule::account_role_count(1), 2);
+        assert_eq!(RBACModule::account_role_count(2), 1);
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(6)
+        );
+        assert_ok!(RBACModule::do_try_state());
+    });
+}
+
+// Migration indexes the names of the roles created before the index
+#[test]
+fn test_migration_index_role_names() {
+    use crate::{migrations::v7::IndexRoleNames, RoleNameIndex};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        let role_id_read = RBACModule::add_role("team.read".as_bytes(), &[], true).unwrap();
+        RBACModule::preassign_role(1, role_id_read).unwrap();
+
+        // Seed the storage as it was before the index
+        let _ = RoleNameIndex::<Test>::clear(u32::MAX, None);
+        StorageVersion::new(6).put::<RBACModule>();
+        assert!(!RBACModule::authorize_name_prefix(&1, b"team."));
+
+        IndexRoleNames::<Test>::on_runtime_upgrade();
+
+        assert!(RBACModule::authorize_name_prefix(&1, b"team."));
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(7)
+        );
+    });
+}
+
+// Index the ranges reserved before `ReservedIds`, so the generated ids skip them
+#[test]
+fn test_migration_index_reserved_ids() {
+    use crate::{migrations::v8::IndexReservedIds, ReservedIds};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(RBACModule::reserve_id_range(1, 9));
+
+        // Seed the storage as it was before the index
+        let _ = ReservedIds::<Test>::clear(u32::MAX, None);
+        StorageVersion::new(7).put::<RBACModule>();
+
+        IndexReservedIds::<Test>::on_runtime_upgrade();
+
+        assert_eq!(RBACModule::add_role("admin".as_bytes(), &[], true), Ok(10));
+        assert_eq!(
+            RBACModule::on_chain_storage_version(),
+            StorageVersion::new(8)
+        );
+    });
+}
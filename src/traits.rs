@@ -1,3 +1,10 @@
+use frame_support::{
+    sp_std::vec::Vec,
+    traits::{Get, Incrementable},
+    weights::Weight,
+    BoundedVec,
+};
+
 // The traits below are created to allow loose coupling between RBAC pallets and its consumers.
 // Ideally they should be placed in some common crate and imported from it by implementers and by comsu,ers.
 
@@ -5,16 +12,184 @@
 pub trait Authorize<AId, RId> {
     /// Authorize the user against some role list
     ///
+    /// The result for an empty list is up to the implementation, see `EmptyRolesAllow` of the pallet.
+    ///
     /// **Parameters**:
     /// - `user`: account to check against the roles
     /// - `roles`: role array to check against
     fn authorize(user: &AId, roles: &[RId]) -> bool;
+
+    /// Authorize the user against some bounded role list, e.g. the one loaded from storage
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `roles`: bounded role list to check against
+    fn authorize_bounded<B: Get<u32>>(user: &AId, roles: &BoundedVec<RId, B>) -> bool {
+        Self::authorize(user, roles.as_slice())
+    }
+
+    /// Authorize the user against some role list, checking the `priority` roles first
+    ///
+    /// The result is the same as for `authorize`, the hint only changes the order of checks,
+    /// so consumers who know which role is usually held can save some reads.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `roles`: role array to check against
+    /// - `priority`: roles from `roles` that should be checked first
+    fn authorize_ordered(user: &AId, roles: &[RId], priority: &[RId]) -> bool {
+        let _ = priority;
+        Self::authorize(user, roles)
+    }
+
+    /// Check the user against each role of the list separately
+    ///
+    /// Bit `i` of the result is set if the user is authorized by `roles[i]` alone.
+    /// Only the first 64 roles are checked, the rest of the list is ignored.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `roles`: role array to check against, up to 64 roles
+    fn authorize_mask(user: &AId, roles: &[RId]) -> u64 {
+        roles
+            .iter()
+            .take(u64::BITS as usize)
+            .enumerate()
+            .filter(|(_, role)| Self::authorize(user, core::slice::from_ref(*role)))
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    /// Find the first role the user is authorized by, evaluating the roles lazily
+    ///
+    /// Each role is checked alone, so unlike `authorize` a denial of a later role
+    /// doesn't fail the match of an earlier one. The roles after the match are not generated.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `roles`: roles to check against, e.g. computed per call
+    ///
+    /// **Returns**: the matched role, if any
+    fn authorize_first_match<I: Iterator<Item = RId>>(user: &AId, roles: I) -> Option<RId> {
+        let mut roles = roles;
+        roles.find(|role| Self::authorize(user, core::slice::from_ref(role)))
+    }
+
+    /// Authorize the user against an expression over roles, e.g. "(A and B) or C"
+    ///
+    /// Each role is checked alone, like in `authorize_mask`, and the checks stop as soon as
+    /// the result is known. Expressions exceeding `RoleExpr::MAX_DEPTH` or `RoleExpr::MAX_NODES`
+    /// are not authorized without checking any role.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the expression
+    /// - `expr`: expression to check against
+    fn authorize_expr(user: &AId, expr: &RoleExpr<RId>) -> bool {
+        expr.is_bounded()
+            && expr.evaluate(&|role: &RId| Self::authorize(user, core::slice::from_ref(role)))
+    }
+
+    /// Authorize the user against some role list, failing with an error if it is not authorized
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `roles`: role array to check against
+    ///
+    /// **Errors**:
+    /// - `NotAuthorized` if the user has none of the roles
+    fn try_authorize(user: &AId, roles: &[RId]) -> Result<(), InterfaceError<RId>> {
+        if Self::authorize(user, roles) {
+            Ok(())
+        } else {
+            Err(InterfaceError::NotAuthorized)
+        }
+    }
+}
+
+/// Expression over roles for `Authorize::authorize_expr`
+///
+/// E.g. "(A and B) or C" is `Or(vec![And(vec![Role(a), Role(b)]), Role(c)])`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RoleExpr<RId> {
+    /// The user should hold the role
+    Role(RId),
+    /// The user should satisfy all of the expressions, an empty list is never satisfied
+    And(Vec<RoleExpr<RId>>),
+    /// The user should satisfy any of the expressions, an empty list is never satisfied
+    Or(Vec<RoleExpr<RId>>),
+}
+
+impl<RId> RoleExpr<RId> {
+    /// Maximum nesting of `And` and `Or`
+    pub const MAX_DEPTH: u32 = 4;
+
+    /// Maximum number of roles, `And` and `Or` in the expression
+    pub const MAX_NODES: u32 = 32;
+
+    /// Check that the expression doesn't exceed `MAX_DEPTH` and `MAX_NODES`.
+    /// It stops at the first node over the limits, so oversized expressions are not walked through.
+    pub fn is_bounded(&self) -> bool {
+        let mut nodes = 0;
+        self.fits(0, &mut nodes)
+    }
+
+    fn fits(&self, depth: u32, nodes: &mut u32) -> bool {
+        *nodes += 1;
+        if *nodes > Self::MAX_NODES {
+            return false;
+        }
+        match self {
+            RoleExpr::Role(_) => true,
+            RoleExpr::And(exprs) | RoleExpr::Or(exprs) => {
+                depth < Self::MAX_DEPTH && exprs.iter().all(|expr| expr.fits(depth + 1, nodes))
+            }
+        }
+    }
+
+    /// Evaluate the expression, `holds` checks a single role
+    pub fn evaluate<F: Fn(&RId) -> bool>(&self, holds: &F) -> bool {
+        match self {
+            RoleExpr::Role(role) => holds(role),
+            RoleExpr::And(exprs) => {
+                !exprs.is_empty() && exprs.iter().all(|expr| expr.evaluate(holds))
+            }
+            RoleExpr::Or(exprs) => exprs.iter().any(|expr| expr.evaluate(holds)),
+        }
+    }
+}
+
+/// Trait describing the authorization by role names
+///
+/// Role names are hashed in the storage, so the prefix can't be looked up directly
+/// and the whole name index is scanned. Use it only off-chain, e.g. through `RbacApi`,
+/// and never from a dispatchable or a hook.
+pub trait AuthorizeByName<AId> {
+    /// Authorize the user against the roles whose names start with the prefix,
+    /// e.g. `team.finance.` matches both `team.finance.read` and `team.finance.write`
+    ///
+    /// It iterates the whole `RoleNameIndex` storage, so it should be called only off-chain.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check against the roles
+    /// - `prefix`: prefix of role names to check against
+    fn authorize_name_prefix(user: &AId, prefix: &[u8]) -> bool;
+}
+
+/// Trait describing the authorization by permissions attached to roles
+pub trait AuthorizePermission<AId, PId> {
+    /// Authorize the user for the permission, e.g. a specific action of the consumer pallet.
+    /// Any of the active roles held by the user should include the permission.
+    ///
+    /// **Parameters**:
+    /// - `user`: account to check
+    /// - `permission`: permission to check the user for
+    fn authorize_permission(user: &AId, permission: &PId) -> bool;
 }
 
 /// Trait describing the add role call
 pub trait AddRole<Id> {
     /// Add a new role to the role list
     /// This should be called only from `GenesisBuild` or `Hooks::on_runtime_upgrade`
+    /// An extrinsic adding roles through it should charge `WeightInfo::add_role` with the length of `granters`.
     ///
     /// **Parameters**:
     /// - `name`: slice of bytes representing the role name
@@ -22,11 +197,85 @@ pub trait AddRole<Id> {
     /// - `can_assign_itself`: if set to true, then after id generation it will be added as a granter to role
     ///
     /// **Returns**: generated role id
+    ///
+    /// **Errors**:
+    /// - `NameTooLong` if the name is too long
+    /// - `GrantersListTooLong` if there are too many granters
+    /// - `RoleNotExist` if one of the granters doesn't exist
     fn add_role(
         name: &[u8],
         granters: &[Id],
         can_assign_itself: bool,
-    ) -> Result<Id, InterfaceError>;
+    ) -> Result<Id, InterfaceError<Id>>;
+}
+
+/// Trait describing the add role call for roles with an owner
+pub trait AddOwnedRole<AId, Id> {
+    /// Add a new role owned by the account, the owner can manage the role without holding its granters
+    /// This should be called only from `GenesisBuild` or `Hooks::on_runtime_upgrade`
+    ///
+    /// **Parameters**:
+    /// - `owner`: account owning the role
+    /// - `name`, `granters`, `can_assign_itself`: same as for `AddRole::add_role`
+    ///
+    /// **Returns**: generated role id
+    ///
+    /// **Errors**:
+    /// - errors of `AddRole::add_role`
+    fn add_owned_role(
+        owner: AId,
+        name: &[u8],
+        granters: &[Id],
+        can_assign_itself: bool,
+    ) -> Result<Id, InterfaceError<Id>>;
+}
+
+/// Trait describing the role granters lookup
+pub trait GranterCount<RId> {
+    /// Get the number of granters of the role, e.g. to size the weight of `Authorize::authorize` against them
+    ///
+    /// **Parameters**:
+    /// - `role`: role to count the granters of
+    ///
+    /// **Returns**: number of the role granters, `0` if there is no such role
+    fn granter_count(role: &RId) -> u32;
+}
+
+/// Trait describing the role id ranges reserved for subsystems
+///
+/// It gives namespacing without instancing: ids of the roles created by `AddRole` skip the reserved ranges,
+/// so only `add_role_in_range` creates the roles inside of them.
+pub trait ReserveIdRange<Id> {
+    /// Reserve a range of role ids
+    /// This should be called only from `GenesisBuild` or `Hooks::on_runtime_upgrade`
+    ///
+    /// **Parameters**:
+    /// - `start`: first id of the range, it is used to refer to the range later
+    /// - `end`: last id of the range
+    ///
+    /// **Errors**:
+    /// - `InvalidRange` if the range is empty, can't be represented by ids or overlaps with another one
+    fn reserve_id_range(start: u128, end: u128) -> Result<(), InterfaceError<Id>>;
+
+    /// Add a new role with the id from the reserved range
+    /// This should be called only from `GenesisBuild` or `Hooks::on_runtime_upgrade`
+    ///
+    /// **Parameters**:
+    /// - `range_start`: start of the reserved range
+    /// - `name`, `granters`, `can_assign_itself`: same as for `AddRole::add_role`
+    ///
+    /// **Returns**: generated role id
+    ///
+    /// **Errors**:
+    /// - `InvalidRange` if there is no range reserved from `range_start`
+    /// - `RangeExhausted` if all ids of the range are taken
+    /// - errors of `AddRole::add_role`
+    fn add_role_in_range(
+        range_start: u128,
+        name: &[u8],
+        granters: &[Id],
+        can_assign_itself: bool,
+    ) -> Result<Id, InterfaceError<Id>>;
 }
 
 /// Trait describing the preassign role call
@@ -40,12 +289,90 @@ pub trait PreassignRole<AId, RId> {
     ///
     /// **Errors**:
     /// - `RoleNotExist` if there is no role for this `role_id`
-    fn preassign_role(user: AId, role: RId) -> Result<(), InterfaceError>;
+    /// - `InvalidGrantee` if the user is rejected as the grantee, e.g. the zero account
+    /// - `TooManyRolesForAccount` if the user already holds the maximum number of roles
+    fn preassign_role(user: AId, role: RId) -> Result<(), InterfaceError<RId>>;
+}
+
+/// Trait notifying consumers about the changes of the roles, e.g. to invalidate their caches of role membership
+///
+/// Handlers must not change the RBAC state: the pallet doesn't re-check the authorization of the ongoing call
+/// after notifying them. Grants and revocations of the removed role fail anyway, as the role doesn't exist anymore.
+pub trait OnRoleChanged<RId> {
+    /// Called when the role is removed, its assignments may still be purged later
+    ///
+    /// **Parameters**:
+    /// - `role`: id of the removed role
+    fn on_role_removed(role: &RId);
+
+    /// Weight of `on_role_removed`, charged by the calls removing roles
+    fn on_role_removed_weight() -> Weight {
+        Weight::zero()
+    }
+}
+
+impl<RId> OnRoleChanged<RId> for () {
+    fn on_role_removed(_role: &RId) {}
+}
+
+/// Facade bundling the traits that consumers usually need, implemented for every type implementing all of them
+///
+/// Use it as a single bound in the consumer's config: `type RBAC: RoleProvider<Self::AccountId, Self::RoleId>;`
+pub trait RoleProvider<AId, RId>:
+    Authorize<AId, RId> + AddRole<RId> + PreassignRole<AId, RId>
+{
+}
+
+impl<AId, RId, T> RoleProvider<AId, RId> for T where
+    T: Authorize<AId, RId> + AddRole<RId> + PreassignRole<AId, RId>
+{
+}
+
+/// Trait describing the generation of role ids
+pub trait AllocateRoleId<Id> {
+    /// Generate the next role id
+    ///
+    /// **Parameters**:
+    /// - `last`: the latest generated id, `RoleIdStart` if there were none
+    /// - `count`: sequential number of the id to generate, starting from 1
+    ///
+    /// Generated ids within a reserved range are skipped: `last` becomes the last id of the range
+    /// and `count` its number, so the ids are expected to grow with both of them.
+    ///
+    /// **Returns**: generated role id or `None` if there are no ids left
+    fn allocate(last: &Id, count: u128) -> Option<Id>;
+}
+
+/// Role id generation for ids implementing `Incrementable`
+pub struct IncrementableIds;
+
+impl<Id: Incrementable + PartialEq> AllocateRoleId<Id> for IncrementableIds {
+    fn allocate(last: &Id, _count: u128) -> Option<Id> {
+        // `increment` saturates at the maximum id, so the ids are exhausted once it stops changing
+        let next = last.increment();
+        (next != *last).then_some(next)
+    }
+}
+
+/// Role id generation for ids that can be built from a `u128` counter
+pub struct SequentialIds;
+
+impl<Id: TryFrom<u128>> AllocateRoleId<Id> for SequentialIds {
+    fn allocate(_last: &Id, count: u128) -> Option<Id> {
+        Id::try_from(count).ok()
+    }
 }
 
 #[derive(Debug, PartialEq)]
-pub enum InterfaceError {
-    RoleNotExist,
+pub enum InterfaceError<RId> {
+    NotAuthorized,
+    RoleNotExist { role: RId },
     NameTooLong { expected: u32, observed: usize },
     GrantersListTooLong { expected: u32, observed: usize },
+    IdsExhausted,
+    InvalidRange,
+    RangeExhausted,
+    InvalidGrantee,
+    TooManyRolesForAccount { max: u32 },
+    NotSelfAssignable { role: RId },
 }
@@ -4,68 +4,613 @@ use frame_support::{
     weights::{constants::RocksDbWeight, Weight},
 };
 
+// Listing the holders of a role: full scan of `Assignments` vs a reverse `RoleMembers` index.
+// Run the comparison with
+// `cargo build --release --features runtime-benchmarks` and
+// `./target/release/node benchmark pallet --pallet pallet_rbac --extrinsic "list_role_holders_*" --steps 50 --repeat 20`
+// and record the results here before choosing the index design.
+// The benchmarks have not been run yet, so there are no results to compare and the index is not chosen,
+// see "Benchmark results" in future.md.
+// - list_role_holders_scan(n): not measured
+// - list_role_holders_index(n): not measured
+//
+// `grant_role(n)` is benchmarked with `n` granters where the caller holds only the last one.
+// `grant_role_batch(n, g, c)` is benchmarked the same way with `g` granters, `c` conflicts of the role and `n` users.
+// `revoke_role_batch(n, g)` is benchmarked with `g` granters and `n` holders of the role.
+// None of the benchmarks has been run on the reference hardware yet: every weight below is
+// a hand-written estimate, not generated output. Replace this file with the output of
+// `./target/release/node benchmark pallet --pallet pallet_rbac --extrinsic "*" --steps 50 --repeat 20 --output weights.rs`
+// once they are run, keeping the `()` implementation.
+
+/// I assume that in general we will check the user against the list of 2 roles
+const TYPICAL_ROLES_CHECKED: u32 = 2;
+
 /// Weight functions needed for pallet_rbac.
 pub trait WeightInfo {
-    fn grant_role() -> Weight;
-    fn revoke_role() -> Weight;
-    fn add_role() -> Weight;
-    fn authorize() -> Weight;
+    fn grant_role(n: u32) -> Weight;
+    fn revoke_role(n: u32) -> Weight;
+    fn add_role(g: u32) -> Weight;
+    fn authorize(n: u32) -> Weight;
+    fn authorize_ordered() -> Weight;
+    fn authorize_name_prefix(r: u32, m: u32) -> Weight;
+    fn set_role_window() -> Weight;
+    fn grant_role_batch(n: u32, g: u32, c: u32) -> Weight;
+    fn protect_role() -> Weight;
+    fn unprotect_role() -> Weight;
+    fn deny_role() -> Weight;
+    fn undeny_role() -> Weight;
+    fn purge_role(n: u32, c: u32, d: u32) -> Weight;
+    fn suspend_account() -> Weight;
+    fn reinstate_account() -> Weight;
+    fn set_conflicts(n: u32) -> Weight;
+    fn revoke_role_batch(n: u32, g: u32) -> Weight;
+    fn set_self_assignable(n: u32) -> Weight;
+    fn transfer_role_ownership() -> Weight;
+    fn grant_role_from(n: u32) -> Weight;
+    fn add_permission() -> Weight;
+    fn remove_permission() -> Weight;
+    fn remove_granter(n: u32) -> Weight;
+    fn set_role_metadata() -> Weight;
+    fn update_membership_root(n: u32) -> Weight;
+    fn sponsor_and_grant(n: u32) -> Weight;
+    fn create_roles_from_template() -> Weight;
+    fn revoke_dependents(d: u32) -> Weight;
+    fn set_dependencies(n: u32) -> Weight;
+    fn set_role_uri(n: u32) -> Weight;
+    fn grant_roles(n: u32, g: u32) -> Weight;
+    fn grant_role_windowed(n: u32) -> Weight;
+    fn reap_expired_assignments(n: u32) -> Weight;
+    fn force_preassign() -> Weight;
 }
 
 /// Weights for pallet_rbac.
-pub struct SubstrateWeight<T>(PhantomData<T>);
+///
+/// `I` is the pallet instance the weights are measured for, so an instanced pallet can use
+/// its own benchmarked weights, e.g. `type WeightInfo = SubstrateWeight<Runtime, Instance1>`.
+/// Until the weights are benchmarked per instance all of them share the estimates below.
+///
+/// Production runtimes must use `SubstrateWeight<Runtime>`, as it charges the storage accesses
+/// with the runtime's `DbWeight`, e.g. ParityDb and RocksDb databases weigh differently.
+pub struct SubstrateWeight<T, I = ()>(PhantomData<(T, I)>);
+
+impl<T: frame_system::Config, I> WeightInfo for SubstrateWeight<T, I> {
+    fn add_role(g: u32) -> Weight {
+        // `g` is the number of the granters, each of them is deduplicated and checked for existence
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(500_000, 0).saturating_mul(g as u64))
+            .saturating_add(T::DbWeight::get().reads(g as u64))
+            .saturating_add(T::DbWeight::get().writes(3_u64))
+    }
 
-impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-    fn add_role() -> Weight {
-        // ideally it should be measured in benchmarks
-        Weight::from_parts(6_000_000, 0).saturating_add(T::DbWeight::get().writes(3_u64))
+    fn authorize(n: u32) -> Weight {
+        // the account is checked for suspension,
+        // each of `n` roles is checked for denial and each assigned role also reads its info to check the active window,
+        // the metrics are counted if they are tracked
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().reads(3 * n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(2_u64, 2_u64))
     }
 
-    fn authorize() -> Weight {
-        // I assume that in general we will check the user against the list of 2 roles
-        Weight::from_parts(6_000_000, 0).saturating_add(T::DbWeight::get().reads(2_u64))
+    fn authorize_ordered() -> Weight {
+        // Worst case is the same as for `authorize`, the hint only saves reads on a hit
+        Self::authorize(TYPICAL_ROLES_CHECKED)
     }
 
-    fn grant_role() -> Weight {
+    fn authorize_name_prefix(r: u32, m: u32) -> Weight {
+        // the whole name index of `r` roles is scanned, then `m` matched roles are checked
         Weight::from_parts(6_000_000, 0)
-            .saturating_add(Self::authorize())
-            .saturating_add(T::DbWeight::get().writes(2_u64))
+            .saturating_add(T::DbWeight::get().reads(r as u64))
+            .saturating_add(T::DbWeight::get().reads(3 * m as u64))
+    }
+
+    fn grant_role(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(T::DbWeight::get().writes(4_u64))
+            .saturating_add(T::DbWeight::get().reads(3_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 2_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn revoke_role(n: u32) -> Weight {
+        // `n` is the number of the role granters, zero if the original granter revokes the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(T::DbWeight::get().writes(4_u64))
+            .saturating_add(T::DbWeight::get().reads(5_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 2_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(3_u64, 1_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 2_u64))
+    }
+
+    fn set_role_window() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+    }
+
+    fn grant_role_batch(n: u32, g: u32, c: u32) -> Weight {
+        // the origin is checked once against `g` granters of the role,
+        // every user is checked against `c` conflicts and then gets the assignment, its granter, holder count and audit log entry written
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(g))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads(c as u64 * n as u64))
+            .saturating_add(T::DbWeight::get().reads(2 * n as u64))
+            .saturating_add(T::DbWeight::get().writes(3 * n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(n as u64, 2 * n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(n as u64, n as u64))
+            .saturating_add(T::DbWeight::get().writes(n as u64))
+    }
+
+    fn protect_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+    }
+
+    fn unprotect_role() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn deny_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn undeny_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().reads_writes(3_u64, 1_u64))
+    }
+
+    fn purge_role(n: u32, c: u32, d: u32) -> Weight {
+        // `n` accounts of the role index or metadata entries are visited,
+        // each account has its assignment, window, granter, denial and revocation removed,
+        // each of `c` conflicts and `d` dependencies and dependents is unlinked from the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul((c + d) as u64))
+            .saturating_add(T::DbWeight::get().reads(7_u64))
+            .saturating_add(T::DbWeight::get().reads(3 * n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes((c + d) as u64, (c + d) as u64))
+            .saturating_add(T::DbWeight::get().writes(3_u64))
+            .saturating_add(T::DbWeight::get().writes(9 * n as u64))
+    }
+
+    fn suspend_account() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn reinstate_account() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn set_conflicts(n: u32) -> Weight {
+        // the role and each of `n` old and `n` new conflicting roles are read and written
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().reads(1_u64 + 2 * n as u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64 + 2 * n as u64))
+    }
+
+    fn revoke_role_batch(n: u32, g: u32) -> Weight {
+        // the origin is checked once against `g` granters of the role,
+        // every user is checked and then loses the assignment, its granter and holder count, the action is audited
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(g))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads(3 * n as u64))
+            .saturating_add(T::DbWeight::get().writes(3 * n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(n as u64, 2 * n as u64))
+            .saturating_add(T::DbWeight::get().writes(n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(3 * n as u64, n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(n as u64, 2 * n as u64))
+    }
+
+    fn set_self_assignable(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn transfer_role_ownership() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn grant_role_from(n: u32) -> Weight {
+        // same as `grant_role` with the activation block written
+        Self::grant_role(n).saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn add_permission() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
             .saturating_add(T::DbWeight::get().reads(2_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
     }
 
-    fn revoke_role() -> Weight {
+    fn remove_permission() -> Weight {
         Weight::from_parts(6_000_000, 0)
-            .saturating_add(Self::authorize())
-            .saturating_add(T::DbWeight::get().writes(2_u64))
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
             .saturating_add(T::DbWeight::get().reads(2_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn remove_granter(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn set_role_metadata() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(T::DbWeight::get().reads(3_u64))
+            .saturating_add(T::DbWeight::get().writes(2_u64))
+    }
+
+    fn update_membership_root(n: u32) -> Weight {
+        // `n` is the number of the assignments, each of them is read and hashed
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads(n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 3_u64))
+    }
+
+    fn sponsor_and_grant(n: u32) -> Weight {
+        // same as `grant_role` with the account providers read and written
+        Self::grant_role(n)
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn create_roles_from_template() -> Weight {
+        // three roles with up to one granter each, the admin assignment and its counters
+        Self::add_role(1)
+            .saturating_mul(3)
+            .saturating_add(T::DbWeight::get().reads_writes(3_u64, 3_u64))
+    }
+
+    fn revoke_dependents(d: u32) -> Weight {
+        // `d` is the number of the roles depending on the revoked one, each of them may be unassigned
+        Weight::from_parts(1_000_000, 0)
+            .saturating_mul(d as u64)
+            .saturating_add(T::DbWeight::get().reads((2 * d) as u64))
+            .saturating_add(T::DbWeight::get().writes((6 * d) as u64))
+    }
+
+    fn set_dependencies(n: u32) -> Weight {
+        // `n` is the number of the dependencies, each of them is checked and linked back to the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().reads((2 * n + 2) as u64))
+            .saturating_add(T::DbWeight::get().writes((n + 1) as u64))
+    }
+
+    fn set_role_uri(n: u32) -> Weight {
+        // `n` is the number of the role granters, zero if the owner sets the pointer
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(T::DbWeight::get().reads(1_u64))
+            .saturating_add(T::DbWeight::get().writes(1_u64))
+    }
+
+    fn grant_roles(n: u32, g: u32) -> Weight {
+        // `n` is the number of the roles, `g` is the number of the granters of each of them
+        Self::grant_role(g).saturating_mul(n as u64)
+    }
+
+    fn grant_role_windowed(n: u32) -> Weight {
+        // same as `grant_role` with both ends of the window written
+        Self::grant_role(n).saturating_add(T::DbWeight::get().writes(2_u64))
+    }
+
+    fn reap_expired_assignments(n: u32) -> Weight {
+        // `n` is the number of the visited assignment windows, each of them may be expired and unassigned
+        Weight::from_parts(2_000_000, 0)
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(T::DbWeight::get().reads((5 * n) as u64))
+            .saturating_add(T::DbWeight::get().writes((8 * n) as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(3 * n as u64, n as u64))
+            .saturating_add(T::DbWeight::get().reads_writes(n as u64, 2 * n as u64))
+    }
+
+    fn force_preassign() -> Weight {
+        // the role, the assignment and its counters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(T::DbWeight::get().reads(4_u64))
+            .saturating_add(T::DbWeight::get().writes(5_u64))
     }
 }
 
-// For backwards compatibility and tests
+// For backwards compatibility and tests.
+// Same estimates as `SubstrateWeight` with the storage accesses always charged as `RocksDbWeight`.
 impl WeightInfo for () {
     /// Storage: TemplateModule Something (r:0 w:1)
     /// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-    fn add_role() -> Weight {
-        // ideally it should be measured in benchmarks
-        Weight::from_parts(6_000_000, 0).saturating_add(RocksDbWeight::get().writes(3_u64))
+    fn add_role(g: u32) -> Weight {
+        // `g` is the number of the granters, each of them is deduplicated and checked for existence
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(500_000, 0).saturating_mul(g as u64))
+            .saturating_add(RocksDbWeight::get().reads(g as u64))
+            .saturating_add(RocksDbWeight::get().writes(3_u64))
+    }
+
+    fn authorize(n: u32) -> Weight {
+        // the account is checked for suspension,
+        // each of `n` roles is checked for denial and each assigned role also reads its info to check the active window,
+        // the metrics are counted if they are tracked
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(3 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(2_u64, 2_u64))
     }
 
-    fn authorize() -> Weight {
-        // I assume that in general we will check the user against the list of 2 roles
-        Weight::from_parts(6_000_000, 0).saturating_add(RocksDbWeight::get().reads(2_u64))
+    fn authorize_ordered() -> Weight {
+        // Worst case is the same as for `authorize`, the hint only saves reads on a hit
+        Self::authorize(TYPICAL_ROLES_CHECKED)
     }
 
-    fn grant_role() -> Weight {
+    fn authorize_name_prefix(r: u32, m: u32) -> Weight {
+        // the whole name index of `r` roles is scanned, then `m` matched roles are checked
         Weight::from_parts(6_000_000, 0)
-            .saturating_add(Self::authorize())
-            .saturating_add(RocksDbWeight::get().writes(2_u64))
+            .saturating_add(RocksDbWeight::get().reads(r as u64))
+            .saturating_add(RocksDbWeight::get().reads(3 * m as u64))
+    }
+
+    fn grant_role(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(RocksDbWeight::get().writes(4_u64))
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 2_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn revoke_role(n: u32) -> Weight {
+        // `n` is the number of the role granters, zero if the original granter revokes the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(RocksDbWeight::get().writes(4_u64))
+            .saturating_add(RocksDbWeight::get().reads(5_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 2_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(3_u64, 1_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 2_u64))
+    }
+
+    fn set_role_window() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+    }
+
+    fn grant_role_batch(n: u32, g: u32, c: u32) -> Weight {
+        // the origin is checked once against `g` granters of the role,
+        // every user is checked against `c` conflicts and then gets the assignment, its granter, holder count and audit log entry written
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(g))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads(c as u64 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads(2 * n as u64))
+            .saturating_add(RocksDbWeight::get().writes(3 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(n as u64, 2 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(n as u64, n as u64))
+            .saturating_add(RocksDbWeight::get().writes(n as u64))
+    }
+
+    fn protect_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+    }
+
+    fn unprotect_role() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn deny_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn undeny_role() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(3_u64, 1_u64))
+    }
+
+    fn purge_role(n: u32, c: u32, d: u32) -> Weight {
+        // `n` accounts of the role index or metadata entries are visited,
+        // each account has its assignment, window, granter, denial and revocation removed,
+        // each of `c` conflicts and `d` dependencies and dependents is unlinked from the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul((c + d) as u64))
+            .saturating_add(RocksDbWeight::get().reads(7_u64))
+            .saturating_add(RocksDbWeight::get().reads(3 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes((c + d) as u64, (c + d) as u64))
+            .saturating_add(RocksDbWeight::get().writes(3_u64))
+            .saturating_add(RocksDbWeight::get().writes(9 * n as u64))
+    }
+
+    fn suspend_account() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn reinstate_account() -> Weight {
+        Weight::from_parts(6_000_000, 0).saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn set_conflicts(n: u32) -> Weight {
+        // the role and each of `n` old and `n` new conflicting roles are read and written
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().reads(1_u64 + 2 * n as u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64 + 2 * n as u64))
+    }
+
+    fn revoke_role_batch(n: u32, g: u32) -> Weight {
+        // the origin is checked once against `g` granters of the role,
+        // every user is checked and then loses the assignment, its granter and holder count, the action is audited
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(g))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(Weight::from_parts(3_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads(3 * n as u64))
+            .saturating_add(RocksDbWeight::get().writes(3 * n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(n as u64, 2 * n as u64))
+            .saturating_add(RocksDbWeight::get().writes(n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(3 * n as u64, n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(n as u64, 2 * n as u64))
+    }
+
+    fn set_self_assignable(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn transfer_role_ownership() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn grant_role_from(n: u32) -> Weight {
+        // same as `grant_role` with the activation block written
+        Self::grant_role(n).saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn add_permission() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
             .saturating_add(RocksDbWeight::get().reads(2_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
     }
 
-    fn revoke_role() -> Weight {
+    fn remove_permission() -> Weight {
         Weight::from_parts(6_000_000, 0)
-            .saturating_add(Self::authorize())
-            .saturating_add(RocksDbWeight::get().writes(2_u64))
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
             .saturating_add(RocksDbWeight::get().reads(2_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn remove_granter(n: u32) -> Weight {
+        // `n` is the number of the role granters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn set_role_metadata() -> Weight {
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(TYPICAL_ROLES_CHECKED))
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().writes(2_u64))
+    }
+
+    fn update_membership_root(n: u32) -> Weight {
+        // `n` is the number of the assignments, each of them is read and hashed
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads(n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 3_u64))
+    }
+
+    fn sponsor_and_grant(n: u32) -> Weight {
+        // same as `grant_role` with the account providers read and written
+        Self::grant_role(n)
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn create_roles_from_template() -> Weight {
+        // three roles with up to one granter each, the admin assignment and its counters
+        Self::add_role(1)
+            .saturating_mul(3)
+            .saturating_add(RocksDbWeight::get().reads_writes(3_u64, 3_u64))
+    }
+
+    fn revoke_dependents(d: u32) -> Weight {
+        // `d` is the number of the roles depending on the revoked one, each of them may be unassigned
+        Weight::from_parts(1_000_000, 0)
+            .saturating_mul(d as u64)
+            .saturating_add(RocksDbWeight::get().reads((2 * d) as u64))
+            .saturating_add(RocksDbWeight::get().writes((6 * d) as u64))
+    }
+
+    fn set_dependencies(n: u32) -> Weight {
+        // `n` is the number of the dependencies, each of them is checked and linked back to the role
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().reads((2 * n + 2) as u64))
+            .saturating_add(RocksDbWeight::get().writes((n + 1) as u64))
+    }
+
+    fn set_role_uri(n: u32) -> Weight {
+        // `n` is the number of the role granters, zero if the owner sets the pointer
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(Self::authorize(n))
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+
+    fn grant_roles(n: u32, g: u32) -> Weight {
+        // `n` is the number of the roles, `g` is the number of the granters of each of them
+        Self::grant_role(g).saturating_mul(n as u64)
+    }
+
+    fn grant_role_windowed(n: u32) -> Weight {
+        // same as `grant_role` with both ends of the window written
+        Self::grant_role(n).saturating_add(RocksDbWeight::get().writes(2_u64))
+    }
+
+    fn reap_expired_assignments(n: u32) -> Weight {
+        // `n` is the number of the visited assignment windows, each of them may be expired and unassigned
+        Weight::from_parts(2_000_000, 0)
+            .saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(1_u64, 1_u64))
+            .saturating_add(RocksDbWeight::get().reads((5 * n) as u64))
+            .saturating_add(RocksDbWeight::get().writes((8 * n) as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(3 * n as u64, n as u64))
+            .saturating_add(RocksDbWeight::get().reads_writes(n as u64, 2 * n as u64))
+    }
+
+    fn force_preassign() -> Weight {
+        // the role, the assignment and its counters
+        Weight::from_parts(6_000_000, 0)
+            .saturating_add(RocksDbWeight::get().reads(4_u64))
+            .saturating_add(RocksDbWeight::get().writes(5_u64))
     }
 }